@@ -0,0 +1,135 @@
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use flashtext2::{CaseMode, KeywordProcessor};
+
+const ARTICLE: &str = include_str!("fixtures/article.txt");
+/// The number of keywords that aren't in the text, so the trie is as big as a real dictionary.
+const FILLER_KEYWORDS: usize = 10_000;
+
+/// Returns the article repeated enough times to be about 1 MB.
+fn text() -> String {
+    ARTICLE.repeat(1_000_000 / ARTICLE.len() + 1)
+}
+
+/// Returns the distinct words and pairs of consecutive words of the article.
+fn article_keywords() -> Vec<String> {
+    let words: Vec<_> = ARTICLE
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect();
+    let mut keywords: Vec<_> = words
+        .iter()
+        .map(|word| word.to_string())
+        .chain(words.windows(2).map(|pair| pair.join(" ")))
+        .collect();
+    keywords.sort();
+    keywords.dedup();
+    keywords
+}
+
+/// Returns a dictionary where one in `step` keywords of the article is kept, the smaller the
+/// step the more keywords are found in the text.
+fn keywords(step: usize) -> Vec<String> {
+    let filler = (0..FILLER_KEYWORDS).map(|i| format!("keyword {i} of {}", i % 97));
+    article_keywords().into_iter().step_by(step).chain(filler).collect()
+}
+
+fn processor(case_mode: CaseMode, step: usize) -> KeywordProcessor {
+    let mut kp = KeywordProcessor::with_case_mode(case_mode);
+    kp.add_keywords_from_iter(keywords(step));
+    kp
+}
+
+fn bench_add_keywords(c: &mut Criterion) {
+    let keywords = keywords(1);
+    let mut group = c.benchmark_group("add_keywords");
+    group.throughput(Throughput::Elements(keywords.len() as u64));
+    for case_mode in [CaseMode::Sensitive, CaseMode::Insensitive] {
+        group.bench_function(format!("{case_mode:?}"), |b| {
+            b.iter_batched(
+                || keywords.clone(),
+                |keywords| {
+                    let mut kp = KeywordProcessor::with_case_mode(case_mode);
+                    kp.add_keywords_from_iter(keywords);
+                    kp
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.bench_function("from_sorted_keywords", |b| {
+        b.iter_batched(
+            || keywords.clone(),
+            KeywordProcessor::from_sorted_keywords,
+            BatchSize::LargeInput,
+        );
+    });
+    group.finish();
+}
+
+fn bench_match_density(c: &mut Criterion) {
+    let text = text();
+    let mut group = c.benchmark_group("extract_keywords/density");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    for (name, step) in [("high", 1), ("medium", 10), ("low", 100)] {
+        let kp = processor(CaseMode::Sensitive, step);
+        group.bench_function(name, |b| b.iter(|| kp.extract_keywords(black_box(&text)).count()));
+    }
+    group.finish();
+}
+
+fn bench_case_mode(c: &mut Criterion) {
+    let text = text();
+    let mut group = c.benchmark_group("extract_keywords/case_mode");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    for case_mode in [CaseMode::Sensitive, CaseMode::AsciiInsensitive, CaseMode::Insensitive] {
+        let kp = processor(case_mode, 10);
+        group.bench_function(format!("{case_mode:?}"), |b| {
+            b.iter(|| kp.extract_keywords(black_box(&text)).count())
+        });
+    }
+    group.finish();
+}
+
+fn bench_backends(c: &mut Criterion) {
+    let text = text();
+    let kp = processor(CaseMode::Sensitive, 10);
+    let double_array = kp.to_double_array();
+    let radix = kp.to_radix_tree();
+    let compiled = kp.freeze();
+
+    let mut group = c.benchmark_group("extract_keywords/backend");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    group.bench_function("trie", |b| b.iter(|| kp.extract_keywords(black_box(&text)).count()));
+    group.bench_function("double_array", |b| {
+        b.iter(|| double_array.extract_keywords(black_box(&text)).count())
+    });
+    group.bench_function("radix", |b| b.iter(|| radix.extract_keywords(black_box(&text)).count()));
+    group.bench_function("compiled", |b| {
+        b.iter(|| compiled.extract_keywords(black_box(&text)).count())
+    });
+    group.finish();
+}
+
+fn bench_replace_keywords(c: &mut Criterion) {
+    let text = text();
+    let mut group = c.benchmark_group("replace_keywords");
+    group.throughput(Throughput::Bytes(text.len() as u64));
+    for (name, step) in [("high", 1), ("low", 100)] {
+        let mut kp = KeywordProcessor::new();
+        kp.add_keywords_with_clean_word_from_iter(
+            keywords(step).iter().map(|keyword| (keyword.as_str(), keyword.to_uppercase())),
+        );
+        group.bench_function(name, |b| b.iter(|| kp.replace_keywords(black_box(&text))));
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_add_keywords,
+    bench_match_density,
+    bench_case_mode,
+    bench_backends,
+    bench_replace_keywords
+);
+criterion_main!(benches);
@@ -0,0 +1,132 @@
+use crate::{
+    AsciiTokenizer, CaseMode, FoldDiacritics, KeywordProcessor, Locale, LocaleCase, Normalization,
+    Normalize, Stem, Tokenizer, WordBoundTokenizer, WordCharTokenizer,
+};
+
+/// Configures a [`KeywordProcessor`] before creating it.
+///
+/// ```
+/// use flashtext2::{CaseMode, KeywordProcessor};
+///
+/// let mut kp = KeywordProcessor::builder()
+///     .case_mode(CaseMode::Insensitive)
+///     .capacity(100)
+///     .build();
+/// kp.add_keyword("Rust");
+/// assert_eq!(kp.extract_keywords("RUST").collect::<Vec<_>>(), ["Rust"]);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct KeywordProcessorBuilder<T = WordBoundTokenizer> {
+    case_mode: CaseMode,
+    capacity: usize,
+    tokenizer: T,
+}
+
+impl KeywordProcessorBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<T> KeywordProcessorBuilder<T> {
+    /// Sets whether the keywords are matched regardless of their case (the default is
+    /// `CaseMode::Sensitive`).
+    pub fn case_mode(mut self, case_mode: CaseMode) -> Self {
+        self.case_mode = case_mode;
+        self
+    }
+
+    /// Reserves room for `capacity` distinct first tokens (i.e. the children of the root of the
+    /// trie).
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// Splits the keywords and the text with `tokenizer`, instead of `WordBoundTokenizer`.
+    pub fn tokenizer<U: Tokenizer>(self, tokenizer: U) -> KeywordProcessorBuilder<U> {
+        KeywordProcessorBuilder {
+            case_mode: self.case_mode,
+            capacity: self.capacity,
+            tokenizer,
+        }
+    }
+
+    /// Splits the keywords and the text into the runs of chars for which `is_word_char` returns
+    /// `true` (and the whitespace and the other chars), see [`WordCharTokenizer`].
+    pub fn word_chars<F>(self, is_word_char: F) -> KeywordProcessorBuilder<WordCharTokenizer<F>>
+    where
+        F: Fn(char) -> bool + Clone + 'static,
+    {
+        self.tokenizer(WordCharTokenizer(is_word_char))
+    }
+
+    /// Normalizes the tokens of the keywords and the text to the Unicode normalization `form`,
+    /// see [`Normalize`].
+    pub fn normalization(self, form: Normalization) -> KeywordProcessorBuilder<Normalize<T>> {
+        KeywordProcessorBuilder {
+            case_mode: self.case_mode,
+            capacity: self.capacity,
+            tokenizer: Normalize::new(form, self.tokenizer),
+        }
+    }
+
+    /// Removes the diacritics from the tokens of the keywords and the text, so that e.g. "café"
+    /// matches "cafe", see [`FoldDiacritics`].
+    pub fn fold_diacritics(self) -> KeywordProcessorBuilder<FoldDiacritics<T>> {
+        KeywordProcessorBuilder {
+            case_mode: self.case_mode,
+            capacity: self.capacity,
+            tokenizer: FoldDiacritics(self.tokenizer),
+        }
+    }
+
+    /// Applies the case rules of `locale` (e.g. the Turkish dotless "ı") to the tokens of the
+    /// keywords and the text, see [`LocaleCase`].
+    pub fn locale(self, locale: Locale) -> KeywordProcessorBuilder<LocaleCase<T>> {
+        KeywordProcessorBuilder {
+            case_mode: self.case_mode,
+            capacity: self.capacity,
+            tokenizer: LocaleCase::new(locale, self.tokenizer),
+        }
+    }
+
+    /// Replaces the tokens of the keywords and the text with their stem (or lemma), as returned
+    /// by `stem`, so that e.g. "run" matches "running", see [`Stem`].
+    pub fn stem<F>(self, stem: F) -> KeywordProcessorBuilder<Stem<F, T>>
+    where
+        F: Fn(&str) -> String,
+    {
+        KeywordProcessorBuilder {
+            case_mode: self.case_mode,
+            capacity: self.capacity,
+            tokenizer: Stem::new(stem, self.tokenizer),
+        }
+    }
+
+    /// Asserts that the keywords and the texts are ASCII, so they are split by `AsciiTokenizer`,
+    /// and `CaseMode::Insensitive` becomes `CaseMode::AsciiInsensitive`, which avoids the
+    /// Unicode rules altogether.
+    ///
+    /// The non-ASCII chars are still handled safely, but they are treated as letters, and
+    /// their case is matched exactly.
+    pub fn ascii(self) -> KeywordProcessorBuilder<AsciiTokenizer> {
+        let case_mode = match self.case_mode {
+            CaseMode::Insensitive => CaseMode::AsciiInsensitive,
+            case_mode => case_mode,
+        };
+        KeywordProcessorBuilder {
+            case_mode,
+            capacity: self.capacity,
+            tokenizer: AsciiTokenizer,
+        }
+    }
+
+    /// Creates an empty processor, whose keywords carry a value of type `V`.
+    ///
+    /// `V` is usually inferred from the keywords that are added, otherwise it must be given
+    /// explicitly, e.g. `build::<String>()`.
+    pub fn build<V>(self) -> KeywordProcessor<V, T> {
+        KeywordProcessor::from_parts(self.case_mode, self.capacity, self.tokenizer)
+    }
+}
@@ -0,0 +1,216 @@
+use std::collections::hash_map::{self, Entry};
+use std::slice;
+use unicase::UniCase;
+use crate::key::{AsciiCaseStr, Key, SensitiveStr, UnicaseStr};
+use crate::trie::NodeId;
+use crate::CaseMode;
+
+/// Past this many children, a node stores them in a hashmap instead of a vector.
+const MAX_SMALL: usize = 4;
+
+/// The children of a node of a `Trie`.
+///
+/// Most of the nodes only have one or two children (e.g. the rest of a single keyword), and an
+/// empty slot of a hashmap already takes as much memory as an entry of a vector, which is short
+/// enough to be searched linearly, so a node only switches to a hashmap once it has more than
+/// `MAX_SMALL` children.
+#[derive(Clone, Debug)]
+pub(crate) enum Children {
+    Small(Vec<(Key, NodeId)>),
+    Map(crate::HashMap<NodeId>),
+}
+
+impl Default for Children {
+    fn default() -> Self {
+        Self::Small(Vec::new())
+    }
+}
+
+impl Children {
+    pub fn with_capacity(capacity: usize) -> Self {
+        if capacity > MAX_SMALL {
+            Self::Map(crate::HashMap::with_capacity_and_hasher(capacity, Default::default()))
+        } else {
+            Self::Small(Vec::with_capacity(capacity))
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Small(children) => children.len(),
+            Self::Map(children) => children.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        match self {
+            Self::Small(children) => Iter::Small(children.iter()),
+            Self::Map(children) => Iter::Map(children.iter()),
+        }
+    }
+
+    /// Returns the child of the (normalized) token, and the key it was inserted with.
+    #[inline]
+    pub fn get(&self, token: &str, case_mode: CaseMode) -> Option<(&Key, NodeId)> {
+        match self {
+            Self::Small(children) => children
+                .iter()
+                .find(|(key, _)| eq(key.as_str(), token, case_mode))
+                .map(|(key, id)| (key, *id)),
+            Self::Map(children) => match case_mode {
+                CaseMode::Sensitive => children.get_key_value(SensitiveStr::new(token)),
+                CaseMode::Insensitive => children.get_key_value(UnicaseStr::new(token)),
+                CaseMode::AsciiInsensitive => children.get_key_value(AsciiCaseStr::new(token)),
+            }
+            .map(|(key, &id)| (key, id)),
+        }
+    }
+
+    /// Returns the child of `key`, or inserts `id` as its child (and calls `on_insert` with
+    /// it) if it's missing.
+    pub fn get_or_insert(&mut self, key: Key, id: NodeId, on_insert: impl FnOnce(&Key)) -> NodeId {
+        if let Self::Small(children) = self {
+            if let Some((_, child)) = children.iter().find(|(k, _)| *k == key) {
+                return *child;
+            }
+            if children.len() < MAX_SMALL {
+                on_insert(&key);
+                children.push((key, id));
+                return id;
+            }
+            self.promote(MAX_SMALL * 2);
+        }
+        let Self::Map(children) = self else { unreachable!() };
+        match children.entry(key) {
+            Entry::Occupied(entry) => *entry.get(),
+            Entry::Vacant(entry) => {
+                on_insert(entry.key());
+                *entry.insert(id)
+            }
+        }
+    }
+
+    /// Removes the child of the (normalized) token, returns it with its key.
+    pub fn remove(&mut self, token: &str, case_mode: CaseMode) -> Option<(Key, NodeId)> {
+        match self {
+            Self::Small(children) => {
+                let idx = children.iter().position(|(key, _)| eq(key.as_str(), token, case_mode))?;
+                Some(children.swap_remove(idx))
+            }
+            Self::Map(children) => match case_mode {
+                CaseMode::Sensitive => children.remove_entry(SensitiveStr::new(token)),
+                CaseMode::Insensitive => children.remove_entry(UnicaseStr::new(token)),
+                CaseMode::AsciiInsensitive => children.remove_entry(AsciiCaseStr::new(token)),
+            },
+        }
+    }
+
+    pub fn retain(&mut self, mut f: impl FnMut(&Key, NodeId) -> bool) {
+        match self {
+            Self::Small(children) => children.retain(|(key, id)| f(key, *id)),
+            Self::Map(children) => children.retain(|key, id| f(key, *id)),
+        }
+    }
+
+    /// Replaces the index of each child with `f(index)`.
+    pub fn map_ids(&mut self, mut f: impl FnMut(NodeId) -> NodeId) {
+        match self {
+            Self::Small(children) => children.iter_mut().for_each(|(_, id)| *id = f(*id)),
+            Self::Map(children) => children.values_mut().for_each(|id| *id = f(*id)),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        match self {
+            Self::Small(children) => children.clear(),
+            Self::Map(children) => children.clear(),
+        }
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        match self {
+            Self::Small(children) if children.len() + additional <= MAX_SMALL => {
+                children.reserve(additional);
+            }
+            Self::Small(children) => {
+                let capacity = children.len() + additional;
+                self.promote(capacity);
+            }
+            Self::Map(children) => children.reserve(additional),
+        }
+    }
+
+    /// Releases the spare capacity, and goes back to a vector if there are few enough children.
+    pub fn shrink_to_fit(&mut self) {
+        match self {
+            Self::Small(children) => children.shrink_to_fit(),
+            Self::Map(children) if children.len() <= MAX_SMALL => {
+                // not collected, which would leave room for a few more children
+                let mut small = Vec::with_capacity(children.len());
+                small.extend(children.drain());
+                *self = Self::Small(small);
+            }
+            Self::Map(children) => children.shrink_to_fit(),
+        }
+    }
+
+    /// Returns an estimate of the heap memory (in bytes) used by the children, not including
+    /// their tokens (which are shared, see `Trie::key()`).
+    pub fn memory_usage(&self) -> usize {
+        let entry_size = std::mem::size_of::<(Key, NodeId)>();
+        match self {
+            Self::Small(children) => children.capacity() * entry_size,
+            // each bucket of a hashmap holds an entry, plus a control byte
+            Self::Map(children) => children.capacity() * (entry_size + 1),
+        }
+    }
+
+    /// Moves the children into a hashmap with room for `capacity` of them.
+    fn promote(&mut self, capacity: usize) {
+        if let Self::Small(children) = self {
+            let mut map = crate::HashMap::with_capacity_and_hasher(capacity, Default::default());
+            map.extend(children.drain(..));
+            *self = Self::Map(map);
+        }
+    }
+}
+
+/// Compares a key with a (normalized) token, like the hashmap does.
+#[inline]
+pub(crate) fn eq(key: &str, token: &str, case_mode: CaseMode) -> bool {
+    match case_mode {
+        CaseMode::Sensitive => key == token,
+        CaseMode::Insensitive => UniCase::unicode(key) == UniCase::unicode(token),
+        CaseMode::AsciiInsensitive => key.eq_ignore_ascii_case(token),
+    }
+}
+
+pub(crate) enum Iter<'a> {
+    Small(slice::Iter<'a, (Key, NodeId)>),
+    Map(hash_map::Iter<'a, Key, NodeId>),
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a Key, NodeId);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Small(iter) => iter.next().map(|(key, id)| (key, *id)),
+            Self::Map(iter) => iter.next().map(|(key, &id)| (key, id)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Self::Small(iter) => iter.size_hint(),
+            Self::Map(iter) => iter.size_hint(),
+        }
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
@@ -0,0 +1,46 @@
+use crate::key::{AsciiCaseStr, Key, SensitiveStr, UnicaseStr};
+use crate::CaseMode;
+
+/// The code of the tokens that aren't part of any keyword (the codes of the others start at 1).
+pub(crate) const NO_CODE: u32 = 0;
+
+/// Numbers each distinct token of the keywords, for the compiled representations of the trie
+/// (which only store the codes), so a token of the text is hashed once, instead of once per
+/// level of the trie.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub(crate) struct TokenCodes(crate::HashMap<u32>);
+
+impl TokenCodes {
+    /// Returns the code of `key` (i.e. a normalized token), giving it the next code if it
+    /// doesn't have one yet.
+    ///
+    /// # Panics
+    ///
+    /// If there are more than `u32::MAX - 1` distinct tokens.
+    pub fn get_or_insert(&mut self, key: &Key) -> u32 {
+        let next_code = u32::try_from(self.0.len() + 1).expect("too many tokens");
+        *self.0.entry(key.clone()).or_insert(next_code)
+    }
+
+    /// Returns the code of the normalized `token`, or `NO_CODE`.
+    #[inline]
+    pub fn get(&self, token: &str, case_mode: CaseMode) -> u32 {
+        let code = match case_mode {
+            CaseMode::Sensitive => self.0.get(SensitiveStr::new(token)),
+            CaseMode::Insensitive => self.0.get(UnicaseStr::new(token)),
+            CaseMode::AsciiInsensitive => self.0.get(AsciiCaseStr::new(token)),
+        };
+        code.copied().unwrap_or(NO_CODE)
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.0.shrink_to_fit();
+    }
+
+    /// Returns an estimate of the heap memory (in bytes) used by the tokens and their codes.
+    pub fn memory_usage(&self) -> usize {
+        let bucket_size = std::mem::size_of::<(Key, u32)>() + 1;
+        let tokens: usize = self.0.keys().map(|key| key.as_str().len()).sum();
+        self.0.capacity() * bucket_size + tokens
+    }
+}
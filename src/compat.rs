@@ -0,0 +1,95 @@
+use std::ops::{Deref, DerefMut};
+use crate::{Tokenizer, WordBoundTokenizer};
+
+/// A [`crate::KeywordProcessor`] that is created with the `CaseMode` of this module.
+///
+/// It dereferences to the inner processor, so all of its methods are available.
+#[derive(Clone, PartialEq, Debug)]
+pub struct KeywordProcessor<V = String, T = WordBoundTokenizer>(crate::KeywordProcessor<V, T>);
+
+impl KeywordProcessor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty processor with room for `capacity` distinct first tokens (i.e. the
+    /// children of the root of the trie) without reallocating.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self(crate::KeywordProcessor::from_parts(super::CASE_MODE, capacity, WordBoundTokenizer))
+    }
+}
+
+impl<V, T: Tokenizer> KeywordProcessor<V, T> {
+    /// Creates an empty processor that splits the keywords and the text with `tokenizer`.
+    pub fn with_tokenizer(tokenizer: T) -> Self {
+        Self(crate::KeywordProcessor::from_parts(super::CASE_MODE, 0, tokenizer))
+    }
+
+    pub fn into_inner(self) -> crate::KeywordProcessor<V, T> {
+        self.0
+    }
+}
+
+impl<V, T: Default> Default for KeywordProcessor<V, T> {
+    fn default() -> Self {
+        Self(crate::KeywordProcessor::from_parts(super::CASE_MODE, 0, T::default()))
+    }
+}
+
+impl<V, T> Deref for KeywordProcessor<V, T> {
+    type Target = crate::KeywordProcessor<V, T>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<V, T> DerefMut for KeywordProcessor<V, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<V, T> From<KeywordProcessor<V, T>> for crate::KeywordProcessor<V, T> {
+    fn from(kp: KeywordProcessor<V, T>) -> Self {
+        kp.0
+    }
+}
+
+impl<A, V, T> Extend<A> for KeywordProcessor<V, T>
+where
+    crate::KeywordProcessor<V, T>: Extend<A>,
+{
+    fn extend<I: IntoIterator<Item = A>>(&mut self, iter: I) {
+        self.0.extend(iter);
+    }
+}
+
+impl<A, V, T: Default> FromIterator<A> for KeywordProcessor<V, T>
+where
+    crate::KeywordProcessor<V, T>: Extend<A>,
+{
+    fn from_iter<I: IntoIterator<Item = A>>(iter: I) -> Self {
+        let mut kp = Self::default();
+        kp.extend(iter);
+        kp
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<V: serde::Serialize, T: Tokenizer> serde::Serialize for KeywordProcessor<V, T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de, V, T> serde::Deserialize<'de> for KeywordProcessor<V, T>
+where
+    V: serde::Deserialize<'de>,
+    T: Tokenizer + Default,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::default().0.deserialize_into(deserializer).map(Self)
+    }
+}
@@ -0,0 +1,440 @@
+use std::collections::VecDeque;
+use std::hash::Hash;
+use fxhash::FxHashMap;
+use crate::codes::{TokenCodes, NO_CODE};
+use crate::{CaseMode, KeywordProcessor, Match, Tokenizer, WordBoundTokenizer};
+
+const ROOT: u32 = 0;
+/// The value index of a state that isn't the end of a keyword.
+const NO_VALUE: u32 = u32::MAX;
+
+/// A read-only copy of a [`KeywordProcessor`] that extracts the keywords in linear time, see
+/// `KeywordProcessor::freeze()`.
+///
+/// `KeywordProcessor` traverses its trie from each token of the text, and when the traversal
+/// doesn't lead to a keyword it starts again from the next token, so a text like
+/// "a a a a a ..." with the keyword "a a a a a b" takes a time that is quadratic in the number of
+/// tokens. Instead, this is an Aho-Corasick automaton of the keywords in reverse, i.e. the trie
+/// of the reversed keywords, where each node has a failure link to the longest suffix of its
+/// path that is also in the trie. The text is traversed once backwards, without ever
+/// restarting, which gives us the longest keyword that starts at each token, and then the
+/// keywords are picked from the start of the text.
+///
+/// The keywords are matched like `KeywordProcessor::extract_keywords()` does, except that
+/// their priorities are ignored (the longest keyword always wins).
+///
+/// ```
+/// use flashtext2::KeywordProcessor;
+///
+/// let mut kp = KeywordProcessor::new();
+/// kp.add_keywords_from_iter(["New York", "New York City", "York"]);
+/// let compiled = kp.freeze();
+///
+/// let text = "New York City and York";
+/// assert_eq!(compiled.extract_keywords(text).collect::<Vec<_>>(), ["New York City", "York"]);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct CompiledKeywordProcessor<V = String, T = WordBoundTokenizer> {
+    codes: TokenCodes,
+    // the transitions of the state `s` are `transitions[offsets[s]..offsets[s + 1]]`, each as
+    // the code of its token and the next state, sorted by code
+    offsets: Vec<u32>,
+    transitions: Vec<(u32, u32)>,
+    // the state of the longest proper suffix of each state that is also in the trie
+    fail: Vec<u32>,
+    // the index in `values` of the keyword that ends at each state
+    value_idx: Vec<u32>,
+    // the index in `values` of the longest keyword that is a suffix of each state, i.e. the
+    // first one on the chain of failure links (including the state itself)
+    output: Vec<u32>,
+    // the value and the number of tokens of each keyword, which are only stored once for all
+    // the keywords that share them after `minimize()`
+    values: Vec<V>,
+    token_counts: Vec<u32>,
+    len: usize,
+    tokenizer: T,
+    case_mode: CaseMode,
+}
+
+impl<V: Clone, T: Tokenizer + Clone> CompiledKeywordProcessor<V, T> {
+    /// # Panics
+    ///
+    /// If the trie has more than `u32::MAX` nodes.
+    pub(crate) fn new(kp: &KeywordProcessor<V, T>) -> Self {
+        let mut codes = TokenCodes::default();
+        // the trie of the reversed keywords, which is only hashed while it's being built
+        let mut children: Vec<FxHashMap<u32, u32>> = vec![FxHashMap::default()];
+        let mut value_idx = vec![NO_VALUE];
+        let mut values = Vec::with_capacity(kp.len());
+        let mut token_counts = Vec::with_capacity(kp.len());
+
+        // the tokens are coded in the order of a breadth-first traversal, so the most common
+        // ones have the smallest codes
+        let mut queue = VecDeque::from([(kp.trie(), Vec::new())]);
+        while let Some((node, path)) = queue.pop_front() {
+            if let Some(value) = node.value() {
+                let mut state = ROOT;
+                for &code in path.iter().rev() {
+                    let next_state = u32::try_from(children.len()).expect("too many nodes");
+                    state = *children[state as usize].entry(code).or_insert(next_state);
+                    if state == next_state {
+                        children.push(FxHashMap::default());
+                        value_idx.push(NO_VALUE);
+                    }
+                }
+                value_idx[state as usize] = values.len() as u32;
+                values.push(value.clone());
+                token_counts.push(path.len() as u32);
+            }
+            for (key, child) in node.children() {
+                let mut child_path = path.clone();
+                child_path.push(codes.get_or_insert(key));
+                queue.push_back((child, child_path));
+            }
+        }
+
+        let (fail, output) = failure_links(&children, &value_idx);
+        let mut offsets = Vec::with_capacity(children.len() + 1);
+        let mut transitions = Vec::with_capacity(children.len() - 1);
+        offsets.push(0);
+        for state_children in children {
+            let start = transitions.len();
+            transitions.extend(state_children);
+            transitions[start..].sort_unstable_by_key(|&(code, _)| code);
+            offsets.push(transitions.len() as u32);
+        }
+        codes.shrink_to_fit();
+        Self {
+            codes,
+            offsets,
+            transitions,
+            fail,
+            value_idx,
+            output,
+            len: values.len(),
+            values,
+            token_counts,
+            tokenizer: kp.tokenizer().clone(),
+            case_mode: kp.case_mode(),
+        }
+    }
+}
+
+/// Returns the failure link and the output of each state of the trie (see the fields of
+/// `CompiledKeywordProcessor`).
+fn failure_links(children: &[FxHashMap<u32, u32>], value_idx: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    let mut fail = vec![ROOT; children.len()];
+    let mut output = vec![NO_VALUE; children.len()];
+    // the states are visited by depth, so the failure links of the shallower ones (which are
+    // the only ones they can point to) are always known
+    let mut queue: VecDeque<u32> = children[ROOT as usize].values().copied().collect();
+    for &state in &queue {
+        output[state as usize] = value_idx[state as usize];
+    }
+    while let Some(state) = queue.pop_front() {
+        for (&code, &child) in &children[state as usize] {
+            let mut suffix = fail[state as usize];
+            let child_fail = loop {
+                if let Some(&suffix_child) = children[suffix as usize].get(&code) {
+                    break suffix_child;
+                }
+                if suffix == ROOT {
+                    break ROOT;
+                }
+                suffix = fail[suffix as usize];
+            };
+            fail[child as usize] = child_fail;
+            output[child as usize] = match value_idx[child as usize] {
+                NO_VALUE => output[child_fail as usize],
+                idx => idx,
+            };
+            queue.push_back(child);
+        }
+    }
+    (fail, output)
+}
+
+impl<V, T: Tokenizer> CompiledKeywordProcessor<V, T> {
+    pub fn tokenizer(&self) -> &T {
+        &self.tokenizer
+    }
+
+    pub fn case_mode(&self) -> CaseMode {
+        self.case_mode
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of states of the automaton (not counting the root), i.e. the number
+    /// of distinct token sequences that end a keyword, unless it was minimized.
+    pub fn node_count(&self) -> usize {
+        self.fail.len() - 1
+    }
+
+    /// Merges the equivalent states of the automaton, i.e. the ones that behave exactly the
+    /// same, which turns the trie into a DAWG (directed acyclic word graph). The extraction
+    /// is unchanged.
+    ///
+    /// The keywords are stored in reverse, so it's their first tokens that are merged, e.g. all
+    /// the variants of a product name that share the same value (see `node_count()`):
+    ///
+    /// ```
+    /// use flashtext2::KeywordProcessor;
+    ///
+    /// let mut kp = KeywordProcessor::new();
+    /// for model in ["12", "13", "14"] {
+    ///     for variant in ["mini", "pro", "pro max"] {
+    ///         kp.add_keyword_with_clean_word(&format!("iphone {model} {variant}"), "iPhone");
+    ///     }
+    /// }
+    /// let mut compiled = kp.freeze();
+    /// let node_count = compiled.node_count();
+    /// compiled.minimize();
+    /// assert!(compiled.node_count() < node_count / 2);
+    ///
+    /// let text = "an iphone 13 pro max and an iphone 12 mini";
+    /// assert_eq!(compiled.extract_keywords(text).count(), 2);
+    /// ```
+    pub fn minimize(&mut self)
+    where
+        V: Eq + Hash,
+    {
+        self.dedup_values();
+        let class = self.equivalence_classes();
+        let class_count = class.iter().max().map_or(0, |&max| max as usize + 1);
+
+        // each class is represented by its first state, so the root stays the first one
+        let mut representatives = vec![u32::MAX; class_count];
+        for (state, &class) in class.iter().enumerate().rev() {
+            representatives[class as usize] = state as u32;
+        }
+        let mut offsets = Vec::with_capacity(class_count + 1);
+        let mut transitions = Vec::new();
+        offsets.push(0);
+        for &state in &representatives {
+            let (start, end) = self.transition_range(state);
+            let state_transitions = self.transitions[start..end].iter();
+            transitions.extend(state_transitions.map(|&(code, t)| (code, class[t as usize])));
+            offsets.push(transitions.len() as u32);
+        }
+        let map = |states: &[u32]| -> Vec<u32> {
+            representatives.iter().map(|&state| states[state as usize]).collect()
+        };
+        self.value_idx = map(&self.value_idx);
+        self.output = map(&self.output);
+        self.fail = map(&self.fail).into_iter().map(|fail| class[fail as usize]).collect();
+        self.offsets = offsets;
+        self.transitions = transitions;
+    }
+
+    /// Stores the keywords that have the same value and number of tokens once.
+    fn dedup_values(&mut self)
+    where
+        V: Eq + Hash,
+    {
+        let mut ids = FxHashMap::default();
+        let mut new_idx = Vec::with_capacity(self.values.len());
+        let mut is_first = Vec::with_capacity(self.values.len());
+        for (value, &token_count) in self.values.iter().zip(&self.token_counts) {
+            let next_id = ids.len() as u32;
+            let id = *ids.entry((value, token_count)).or_insert(next_id);
+            new_idx.push(id);
+            is_first.push(id == next_id);
+        }
+        drop(ids);
+
+        let values = std::mem::take(&mut self.values).into_iter().zip(&is_first);
+        self.values = values.filter(|(_, &first)| first).map(|(value, _)| value).collect();
+        let token_counts = self.token_counts.iter().zip(&is_first);
+        self.token_counts = token_counts.filter(|(_, &first)| first).map(|(&n, _)| n).collect();
+        for idx in self.value_idx.iter_mut().chain(&mut self.output) {
+            if *idx != NO_VALUE {
+                *idx = new_idx[*idx as usize];
+            }
+        }
+    }
+
+    /// Returns the class of each state, where two states are in the same class if they
+    /// have the same keyword and output, and their failure links and transitions lead to the
+    /// same classes.
+    ///
+    /// The states are first split by their keyword and output, and then the classes are
+    /// refined until they are stable (Moore's algorithm).
+    fn equivalence_classes(&self) -> Vec<u32> {
+        let mut ids = FxHashMap::default();
+        let mut class: Vec<u32> = (0..self.fail.len())
+            .map(|state| {
+                let next_id = ids.len() as u32;
+                *ids.entry((self.value_idx[state], self.output[state])).or_insert(next_id)
+            })
+            .collect();
+        let mut class_count = ids.len();
+        loop {
+            let mut ids = FxHashMap::default();
+            let refined: Vec<u32> = (0..self.fail.len())
+                .map(|state| {
+                    let (start, end) = self.transition_range(state as u32);
+                    let transitions: Vec<_> = self.transitions[start..end]
+                        .iter()
+                        .map(|&(code, t)| (code, class[t as usize]))
+                        .collect();
+                    let fail = class[self.fail[state] as usize];
+                    let next_id = ids.len() as u32;
+                    *ids.entry((class[state], fail, transitions)).or_insert(next_id)
+                })
+                .collect();
+            class = refined;
+            if ids.len() == class_count {
+                return class;
+            }
+            class_count = ids.len();
+        }
+    }
+
+    #[inline]
+    fn transition_range(&self, state: u32) -> (usize, usize) {
+        let start = self.offsets[state as usize] as usize;
+        let end = self.offsets[state as usize + 1] as usize;
+        (start, end)
+    }
+
+    /// Returns an estimate of the heap memory (in bytes) used by the processor, where
+    /// `value_size` returns the heap memory owned by a value, see
+    /// `KeywordProcessor::memory_usage_with()`.
+    pub fn memory_usage_with(&self, value_size: impl Fn(&V) -> usize) -> usize {
+        let values: usize = self.values.iter().map(value_size).sum();
+        self.codes.memory_usage()
+            + self.transitions.capacity() * std::mem::size_of::<(u32, u32)>()
+            + self.offsets.capacity() * std::mem::size_of::<u32>() * 4
+            + self.token_counts.capacity() * std::mem::size_of::<u32>()
+            + self.values.capacity() * std::mem::size_of::<V>()
+            + values
+    }
+
+    /// Returns the code of the token (of a text or a keyword), or `NO_CODE`.
+    #[inline]
+    fn code(&self, token: &str) -> u32 {
+        self.codes.get(&self.tokenizer.normalize(token), self.case_mode)
+    }
+
+    #[inline]
+    fn child(&self, state: u32, code: u32) -> Option<u32> {
+        let (start, end) = self.transition_range(state);
+        let transitions = &self.transitions[start..end];
+        let idx = transitions.binary_search_by_key(&code, |&(code, _)| code).ok()?;
+        Some(transitions[idx].1)
+    }
+
+    /// Returns the state of the longest suffix of `state` followed by `code` that is in the
+    /// trie.
+    #[inline]
+    fn next_state(&self, mut state: u32, code: u32) -> u32 {
+        if code == NO_CODE {
+            return ROOT;
+        }
+        loop {
+            if let Some(child) = self.child(state, code) {
+                return child;
+            }
+            if state == ROOT {
+                return ROOT;
+            }
+            state = self.fail[state as usize];
+        }
+    }
+
+    pub fn contains<S: AsRef<str> + ?Sized>(&self, word: &S) -> bool {
+        self.get_value(word).is_some()
+    }
+
+    pub fn get_value<S: AsRef<str> + ?Sized>(&self, word: &S) -> Option<&V> {
+        let tokens: Vec<_> = self.tokenizer.tokenize(word.as_ref()).collect();
+        let mut state = ROOT;
+        for &(_, token) in tokens.iter().rev() {
+            state = self.child(state, self.code(token))?;
+        }
+        self.values.get(self.value_idx[state as usize] as usize)
+    }
+
+    /// Returns the keywords in the text, each as the matched text, its value and its span.
+    ///
+    /// The whole text is tokenized (and traversed) before the first keyword is returned.
+    pub fn extract_keywords_with_value<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a V, usize, usize)> + 'a {
+        let mut spans = Vec::new();
+        let mut codes = Vec::new();
+        for (start, token) in self.tokenizer.tokenize(text) {
+            spans.push((start, start + token.len()));
+            codes.push(self.code(token));
+        }
+        // the longest keyword that starts at each token
+        let mut longest = codes;
+        let mut state = ROOT;
+        for code in longest.iter_mut().rev() {
+            state = self.next_state(state, *code);
+            *code = self.output[state as usize];
+        }
+        CompiledExtractor { kp: self, spans, longest, idx: 0 }
+            .map(|(value, start, end)| (&text[start..end], value, start, end))
+    }
+}
+
+impl<T: Tokenizer> CompiledKeywordProcessor<String, T> {
+    /// Returns an estimate of the heap memory (in bytes) used by the processor, see
+    /// `memory_usage_with()`.
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage_with(String::capacity)
+    }
+
+    pub fn get_clean_word<S: AsRef<str> + ?Sized>(&self, word: &S) -> Option<&str> {
+        self.get_value(word).map(String::as_str)
+    }
+
+    pub fn extract_keywords<'a>(&'a self, text: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.extract_keywords_with_span(text).map(|m| m.clean_word)
+    }
+
+    pub fn extract_keywords_with_span<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        self.extract_keywords_with_value(text)
+            .map(|(_, clean_word, start, end)| {
+                Match::new(&text[start..end], clean_word, start, end)
+            })
+    }
+}
+
+struct CompiledExtractor<'a, V, T> {
+    kp: &'a CompiledKeywordProcessor<V, T>,
+    spans: Vec<(usize, usize)>,
+    // the index in `values` of the longest keyword that starts at each token
+    longest: Vec<u32>,
+    idx: usize,
+}
+
+impl<'a, V, T> Iterator for CompiledExtractor<'a, V, T> {
+    type Item = (&'a V, usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(&value_idx) = self.longest.get(self.idx) {
+            if value_idx != NO_VALUE {
+                let token_count = self.kp.token_counts[value_idx as usize] as usize;
+                let start = self.spans[self.idx].0;
+                let end = self.spans[self.idx + token_count - 1].1;
+                self.idx += token_count;
+                return Some((&self.kp.values[value_idx as usize], start, end));
+            }
+            self.idx += 1;
+        }
+        None
+    }
+}
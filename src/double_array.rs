@@ -0,0 +1,265 @@
+use std::collections::VecDeque;
+use crate::codes::{TokenCodes, NO_CODE};
+use crate::{CaseMode, KeywordProcessor, Match, Tokenizer, WordBoundTokenizer};
+
+/// The `check` of a slot that isn't used by any state.
+const FREE: u32 = u32::MAX;
+/// The value index of a state that isn't the end of a keyword.
+const NO_VALUE: u32 = u32::MAX;
+
+/// A read-only copy of a [`KeywordProcessor`], whose trie is stored as a double-array, see
+/// `KeywordProcessor::to_double_array()`.
+///
+/// Each distinct token of the keywords is given a number (its code), so a token of the text
+/// is looked up in a single hashmap, and then the trie is traversed by indexing two arrays:
+/// the child of the state `s` for the code `c` is the state `base[s] + c`, if its `check` is
+/// `s`. That takes a lot less memory than a hashmap in each node, and it's more cache
+/// friendly.
+///
+/// The keywords are matched like `KeywordProcessor::extract_keywords()` does, except that
+/// their priorities are ignored (the longest keyword always wins).
+///
+/// ```
+/// use flashtext2::KeywordProcessor;
+///
+/// let mut kp = KeywordProcessor::new();
+/// kp.add_keywords_from_iter(["New York", "New York City", "York"]);
+/// let da = kp.to_double_array();
+///
+/// let text = "New York City and York";
+/// assert_eq!(da.extract_keywords(text).collect::<Vec<_>>(), ["New York City", "York"]);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct DoubleArrayKeywordProcessor<V = String, T = WordBoundTokenizer> {
+    // the codes are given in the order of a breadth-first traversal of the trie, so the
+    // tokens that start the keywords have the smallest ones, which keeps the arrays short
+    codes: TokenCodes,
+    base: Vec<u32>,
+    check: Vec<u32>,
+    // the index in `values` of the keyword that ends at each state
+    value_idx: Vec<u32>,
+    values: Vec<V>,
+    tokenizer: T,
+    case_mode: CaseMode,
+}
+
+impl<V: Clone, T: Tokenizer + Clone> DoubleArrayKeywordProcessor<V, T> {
+    /// # Panics
+    ///
+    /// If the trie has more than `u32::MAX - 1` nodes.
+    pub(crate) fn new(kp: &KeywordProcessor<V, T>) -> Self {
+        let mut da = Self {
+            codes: Default::default(),
+            base: vec![0],
+            // the root has no parent, but its slot must not be free
+            check: vec![0],
+            value_idx: vec![NO_VALUE],
+            values: Vec::with_capacity(kp.len()),
+            tokenizer: kp.tokenizer().clone(),
+            case_mode: kp.case_mode(),
+        };
+        // the first slot that may be free, so we don't look for a base from the start
+        let mut first_free = 1;
+        let mut queue = VecDeque::from([(kp.trie(), 0)]);
+        while let Some((node, state)) = queue.pop_front() {
+            if let Some(value) = node.value() {
+                da.value_idx[state as usize] = da.values.len() as u32;
+                da.values.push(value.clone());
+            }
+            if !node.has_children() {
+                continue;
+            }
+            let mut children: Vec<_> = node
+                .children()
+                .map(|(key, child)| (da.codes.get_or_insert(key), child))
+                .collect();
+            children.sort_unstable_by_key(|&(code, _)| code);
+
+            while da.check.get(first_free).is_some_and(|&check| check != FREE) {
+                first_free += 1;
+            }
+            let base = da.find_base(&children, first_free);
+            da.base[state as usize] = base;
+            for (code, child) in children {
+                let child_state = base.checked_add(code).filter(|&s| s != FREE);
+                let child_state = child_state.expect("too many nodes");
+                da.resize((child_state as usize) + 1);
+                da.check[child_state as usize] = state;
+                queue.push_back((child, child_state));
+            }
+        }
+        da.shrink_to_fit();
+        da
+    }
+
+    /// Returns the smallest base (at least `first_free` minus the first code) for which all
+    /// the slots of the `children` are free.
+    fn find_base<C>(&self, children: &[(u32, C)], first_free: usize) -> u32 {
+        let first_code = children[0].0 as usize;
+        let is_free = |slot: usize| self.check.get(slot).is_none_or(|&check| check == FREE);
+        let mut slot = first_free.max(first_code);
+        loop {
+            let base = slot - first_code;
+            if children.iter().all(|&(code, _)| is_free(base + code as usize)) {
+                return u32::try_from(base).expect("too many nodes");
+            }
+            slot += 1;
+            while !is_free(slot) {
+                slot += 1;
+            }
+        }
+    }
+
+    fn resize(&mut self, len: usize) {
+        if self.check.len() < len {
+            self.base.resize(len, 0);
+            self.check.resize(len, FREE);
+            self.value_idx.resize(len, NO_VALUE);
+        }
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.codes.shrink_to_fit();
+        self.base.shrink_to_fit();
+        self.check.shrink_to_fit();
+        self.value_idx.shrink_to_fit();
+        self.values.shrink_to_fit();
+    }
+}
+
+impl<V, T: Tokenizer> DoubleArrayKeywordProcessor<V, T> {
+    pub fn tokenizer(&self) -> &T {
+        &self.tokenizer
+    }
+
+    pub fn case_mode(&self) -> CaseMode {
+        self.case_mode
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns an estimate of the heap memory (in bytes) used by the processor, where
+    /// `value_size` returns the heap memory owned by a value, see
+    /// `KeywordProcessor::memory_usage_with()`.
+    pub fn memory_usage_with(&self, value_size: impl Fn(&V) -> usize) -> usize {
+        let values: usize = self.values.iter().map(value_size).sum();
+        self.codes.memory_usage()
+            + self.check.capacity() * std::mem::size_of::<u32>() * 3
+            + self.values.capacity() * std::mem::size_of::<V>()
+            + values
+    }
+
+    /// Returns the code of the token (of a text or a keyword), or `NO_CODE`.
+    #[inline]
+    fn code(&self, token: &str) -> u32 {
+        self.codes.get(&self.tokenizer.normalize(token), self.case_mode)
+    }
+
+    #[inline]
+    fn child(&self, state: u32, code: u32) -> Option<u32> {
+        if code == NO_CODE {
+            return None;
+        }
+        let child_state = self.base[state as usize] as usize + code as usize;
+        (self.check.get(child_state) == Some(&state)).then_some(child_state as u32)
+    }
+
+    #[inline]
+    fn value(&self, state: u32) -> Option<&V> {
+        self.values.get(self.value_idx[state as usize] as usize)
+    }
+
+    pub fn contains<S: AsRef<str> + ?Sized>(&self, word: &S) -> bool {
+        self.get_value(word).is_some()
+    }
+
+    pub fn get_value<S: AsRef<str> + ?Sized>(&self, word: &S) -> Option<&V> {
+        let mut state = 0;
+        for (_, token) in self.tokenizer.tokenize(word.as_ref()) {
+            state = self.child(state, self.code(token))?;
+        }
+        self.value(state)
+    }
+
+    /// Returns the keywords in the text, each as the matched text, its value and its span.
+    pub fn extract_keywords_with_value<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a V, usize, usize)> + 'a {
+        let tokens = self
+            .tokenizer
+            .tokenize(text)
+            .map(|(start, token)| (start, start + token.len(), self.code(token)))
+            .collect();
+        DoubleArrayExtractor { da: self, tokens, idx: 0 }
+            .map(|(value, start, end)| (&text[start..end], value, start, end))
+    }
+}
+
+impl<T: Tokenizer> DoubleArrayKeywordProcessor<String, T> {
+    /// Returns an estimate of the heap memory (in bytes) used by the processor, see
+    /// `memory_usage_with()`.
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage_with(String::capacity)
+    }
+
+    pub fn get_clean_word<S: AsRef<str> + ?Sized>(&self, word: &S) -> Option<&str> {
+        self.get_value(word).map(String::as_str)
+    }
+
+    pub fn extract_keywords<'a>(&'a self, text: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.extract_keywords_with_span(text).map(|m| m.clean_word)
+    }
+
+    pub fn extract_keywords_with_span<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        self.extract_keywords_with_value(text)
+            .map(|(_, clean_word, start, end)| {
+                Match::new(&text[start..end], clean_word, start, end)
+            })
+    }
+}
+
+struct DoubleArrayExtractor<'a, V, T> {
+    da: &'a DoubleArrayKeywordProcessor<V, T>,
+    // the span and the code of each token
+    tokens: Vec<(usize, usize, u32)>,
+    idx: usize,
+}
+
+impl<'a, V, T: Tokenizer> Iterator for DoubleArrayExtractor<'a, V, T> {
+    type Item = (&'a V, usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.idx < self.tokens.len() {
+            let start_idx = self.idx;
+            let mut state = 0;
+            let mut longest = None;
+            for (idx, &(_, end, code)) in self.tokens.iter().enumerate().skip(start_idx) {
+                let Some(child) = self.da.child(state, code) else {
+                    break;
+                };
+                state = child;
+                if let Some(value) = self.da.value(state) {
+                    longest = Some((value, end, idx + 1));
+                }
+            }
+            match longest {
+                Some((value, end, next_idx)) => {
+                    self.idx = next_idx;
+                    return Some((value, self.tokens[start_idx].0, end));
+                }
+                None => self.idx += 1,
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,273 @@
+use crate::codes::{TokenCodes, NO_CODE};
+use crate::trie::ROOT;
+use crate::{CaseMode, KeywordProcessor, Match, Tokenizer, WordBoundTokenizer};
+
+/// The value index of a node that isn't the end of a keyword.
+const NO_VALUE: u32 = u32::MAX;
+
+/// A [`KeywordProcessor`] that can't be modified anymore, whose trie is stored in a few flat
+/// arrays, see `KeywordProcessor::into_frozen()`.
+///
+/// Each distinct token of the keywords is given a number (its code), and the nodes are
+/// numbered in breadth-first order, so the children of a node are consecutive, and sorted by
+/// the code of their token: a child is found with a binary search over a slice, instead of a
+/// hashmap in each node. That takes a lot less memory than the trie of the processor, and
+/// it's more cache friendly.
+///
+/// Unlike the other read-only backends, the priorities are kept (see
+/// `KeywordProcessor::add_keyword_with_priority()`), so the keywords are extracted exactly like
+/// `KeywordProcessor::extract_keywords()` does.
+///
+/// It's `Send + Sync` (as long as the values and the tokenizer are), so it can be shared
+/// between threads in an `Arc`.
+///
+/// ```
+/// use flashtext2::KeywordProcessor;
+///
+/// let mut kp = KeywordProcessor::new();
+/// kp.add_keywords_from_iter(["New York", "New York City", "York"]);
+/// let frozen = kp.into_frozen();
+///
+/// let text = "New York City and York";
+/// assert_eq!(frozen.extract_keywords(text).collect::<Vec<_>>(), ["New York City", "York"]);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct FrozenKeywordProcessor<V = String, T = WordBoundTokenizer> {
+    codes: TokenCodes,
+    // the children of the node `n` are the nodes `first_child[n]..first_child[n + 1]`
+    first_child: Vec<u32>,
+    // the code of the token that leads to each node (the root has `NO_CODE`)
+    labels: Vec<u32>,
+    // the index in `values` of the keyword that ends at each node
+    value_idx: Vec<u32>,
+    values: Vec<V>,
+    // the priority of each value, empty if none of them has one
+    priorities: Vec<u32>,
+    tokenizer: T,
+    case_mode: CaseMode,
+}
+
+impl<V, T: Tokenizer> FrozenKeywordProcessor<V, T> {
+    pub(crate) fn new(kp: KeywordProcessor<V, T>) -> Self {
+        let case_mode = kp.case_mode();
+        let (mut trie, tokenizer) = kp.into_parts();
+        trie.prune();
+        let node_count = trie.node_count() + 1;
+        let mut frozen = Self {
+            codes: Default::default(),
+            first_child: Vec::with_capacity(node_count + 1),
+            labels: Vec::with_capacity(node_count),
+            value_idx: Vec::with_capacity(node_count),
+            values: Vec::new(),
+            priorities: Vec::new(),
+            tokenizer,
+            case_mode,
+        };
+        frozen.labels.push(NO_CODE);
+        // the ids of the nodes of the trie, in the order of their new number
+        let mut order = vec![ROOT];
+        let mut idx = 0;
+        while let Some(&id) = order.get(idx) {
+            idx += 1;
+            frozen.first_child.push(order.len() as u32);
+            let node = trie.node_mut(id);
+            let mut children: Vec<_> = node
+                .children
+                .iter()
+                .map(|(key, child)| (frozen.codes.get_or_insert(key), child))
+                .collect();
+            children.sort_unstable_by_key(|&(code, _)| code);
+            for (code, child) in children {
+                frozen.labels.push(code);
+                order.push(child);
+            }
+            match node.value.take() {
+                Some(value) => {
+                    frozen.value_idx.push(frozen.values.len() as u32);
+                    frozen.values.push(value);
+                    frozen.priorities.push(node.priority);
+                }
+                None => frozen.value_idx.push(NO_VALUE),
+            }
+        }
+        frozen.first_child.push(order.len() as u32);
+        if frozen.priorities.iter().all(|&priority| priority == 0) {
+            frozen.priorities = Vec::new();
+        }
+        frozen.codes.shrink_to_fit();
+        frozen.values.shrink_to_fit();
+        frozen
+    }
+
+    pub fn tokenizer(&self) -> &T {
+        &self.tokenizer
+    }
+
+    pub fn case_mode(&self) -> CaseMode {
+        self.case_mode
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the number of nodes (not counting the root), the ones that don't lead to any
+    /// keyword were removed.
+    pub fn node_count(&self) -> usize {
+        self.labels.len() - 1
+    }
+
+    /// Returns an estimate of the heap memory (in bytes) used by the processor, where
+    /// `value_size` returns the heap memory owned by a value, see
+    /// `KeywordProcessor::memory_usage_with()`.
+    pub fn memory_usage_with(&self, value_size: impl Fn(&V) -> usize) -> usize {
+        let values: usize = self.values.iter().map(value_size).sum();
+        self.codes.memory_usage()
+            + (self.first_child.capacity() + self.labels.capacity() + self.value_idx.capacity())
+                * std::mem::size_of::<u32>()
+            + self.priorities.capacity() * std::mem::size_of::<u32>()
+            + self.values.capacity() * std::mem::size_of::<V>()
+            + values
+    }
+
+    /// Returns the code of the token (of a text or a keyword), or `NO_CODE`.
+    #[inline]
+    fn code(&self, token: &str) -> u32 {
+        self.codes.get(&self.tokenizer.normalize(token), self.case_mode)
+    }
+
+    #[inline]
+    fn child(&self, node: u32, code: u32) -> Option<u32> {
+        if code == NO_CODE {
+            return None;
+        }
+        let start = self.first_child[node as usize] as usize;
+        let end = self.first_child[node as usize + 1] as usize;
+        let idx = self.labels[start..end].binary_search(&code).ok()?;
+        Some((start + idx) as u32)
+    }
+
+    #[inline]
+    fn value_idx(&self, node: u32) -> Option<usize> {
+        let idx = self.value_idx[node as usize];
+        (idx != NO_VALUE).then_some(idx as usize)
+    }
+
+    pub fn contains<S: AsRef<str> + ?Sized>(&self, word: &S) -> bool {
+        self.get_value(word).is_some()
+    }
+
+    pub fn get_value<S: AsRef<str> + ?Sized>(&self, word: &S) -> Option<&V> {
+        let mut node = ROOT;
+        for (_, token) in self.tokenizer.tokenize(word.as_ref()) {
+            node = self.child(node, self.code(token))?;
+        }
+        self.value_idx(node).map(|idx| &self.values[idx])
+    }
+
+    /// Returns the keywords in the text, each as the matched text, its value and its span.
+    pub fn extract_keywords_with_value<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a V, usize, usize)> + 'a {
+        let tokens = self
+            .tokenizer
+            .tokenize(text)
+            .map(|(start, token)| (start, start + token.len(), self.code(token)))
+            .collect();
+        FrozenExtractor { frozen: self, tokens, idx: 0 }
+            .map(|(value, start, end)| (&text[start..end], value, start, end))
+    }
+}
+
+impl<T: Tokenizer> FrozenKeywordProcessor<String, T> {
+    /// Returns an estimate of the heap memory (in bytes) used by the processor, see
+    /// `memory_usage_with()`.
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage_with(String::capacity)
+    }
+
+    pub fn get_clean_word<S: AsRef<str> + ?Sized>(&self, word: &S) -> Option<&str> {
+        self.get_value(word).map(String::as_str)
+    }
+
+    pub fn extract_keywords<'a>(&'a self, text: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.extract_keywords_with_span(text).map(|m| m.clean_word)
+    }
+
+    pub fn extract_keywords_with_span<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        self.extract_keywords_with_value(text)
+            .map(|(keyword, clean_word, start, end)| Match::new(keyword, clean_word, start, end))
+    }
+}
+
+struct FrozenExtractor<'a, V, T> {
+    frozen: &'a FrozenKeywordProcessor<V, T>,
+    // the span and the code of each token
+    tokens: Vec<(usize, usize, u32)>,
+    idx: usize,
+}
+
+impl<'a, V, T: Tokenizer> FrozenExtractor<'a, V, T> {
+    /// Returns the keyword with the highest priority (and then the longest) that starts at the
+    /// token `start_idx` and ends before `limit`, as its value index, its priority and the
+    /// index of the token after it.
+    fn step(&self, start_idx: usize, limit: usize) -> Option<(usize, u32, usize)> {
+        let mut node = ROOT;
+        let mut best: Option<(usize, u32, usize)> = None;
+        for (idx, &(_, _, code)) in self.tokens[..limit].iter().enumerate().skip(start_idx) {
+            let Some(child) = self.frozen.child(node, code) else {
+                break;
+            };
+            node = child;
+            if let Some(value_idx) = self.frozen.value_idx(node) {
+                let priority = self.frozen.priorities.get(value_idx).copied().unwrap_or(0);
+                // a longer keyword only replaces the current one if its priority isn't lower
+                if best.is_none_or(|(_, best_priority, _)| priority >= best_priority) {
+                    best = Some((value_idx, priority, idx + 1));
+                }
+            }
+        }
+        best
+    }
+}
+
+impl<'a, V, T: Tokenizer> Iterator for FrozenExtractor<'a, V, T> {
+    type Item = (&'a V, usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.tokens.len();
+        while self.idx < len {
+            // like `KeywordProcessor`, the keywords that start inside the current one are only
+            // looked for if there are priorities, until we find one with a higher priority, and
+            // then we only consider the keywords that end before it
+            let mut limit = len;
+            while let Some((value_idx, priority, next_idx)) = self.step(self.idx, limit) {
+                let beaten_at = match self.frozen.priorities.is_empty() {
+                    true => None,
+                    false => (self.idx + 1..next_idx).find(|&idx| {
+                        self.step(idx, len)
+                            .is_some_and(|(_, other_priority, _)| other_priority > priority)
+                    }),
+                };
+                match beaten_at {
+                    Some(idx) => limit = idx,
+                    None => {
+                        let span = (self.tokens[self.idx].0, self.tokens[next_idx - 1].1);
+                        self.idx = next_idx;
+                        return Some((&self.frozen.values[value_idx], span.0, span.1));
+                    }
+                }
+            }
+            self.idx += 1;
+        }
+        None
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+/// A small ID of a string stored in an [`Interner`].
+///
+/// It's meant to be used as the value of the keywords, so that many keywords that share the
+/// same clean word only store it once, and each node only holds 4 bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// Returns the index of the string in the interner (i.e. the order it was added in).
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Stores each distinct string once, and identifies it with a [`Symbol`].
+///
+/// ```
+/// use flashtext2::{Interner, KeywordProcessor, Symbol};
+///
+/// let mut interner = Interner::new();
+/// let mut kp = KeywordProcessor::<Symbol>::default();
+/// for (word, category) in [("py", "language"), ("rs", "language"), ("linux", "os")] {
+///     kp.add_keyword_with_value(word, interner.intern(category));
+/// }
+/// assert_eq!(interner.len(), 2);
+///
+/// let categories: Vec<_> = kp
+///     .extract_keywords_with_value("py on linux")
+///     .map(|(_, &symbol, _, _)| interner.resolve(symbol))
+///     .collect();
+/// assert_eq!(categories, ["language", "os"]);
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct Interner {
+    // the strings are shared between the map and the vector, so they are only stored once
+    symbols: std::collections::HashMap<Arc<str>, Symbol, fxhash::FxBuildHasher>,
+    strings: Vec<Arc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the symbol of `string`, adding it to the interner if it isn't there yet.
+    ///
+    /// # Panics
+    ///
+    /// If there are more than `u32::MAX` distinct strings.
+    pub fn intern(&mut self, string: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(string) {
+            return symbol;
+        }
+        let symbol = Symbol(u32::try_from(self.strings.len()).expect("too many strings"));
+        let string: Arc<str> = string.into();
+        self.strings.push(string.clone());
+        self.symbols.insert(string, symbol);
+        symbol
+    }
+
+    /// Returns the symbol of `string`, if it was interned.
+    pub fn get(&self, string: &str) -> Option<Symbol> {
+        self.symbols.get(string).copied()
+    }
+
+    /// Returns the string of `symbol`.
+    ///
+    /// # Panics
+    ///
+    /// If `symbol` comes from another interner (with fewer strings).
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.strings[symbol.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
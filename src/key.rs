@@ -0,0 +1,165 @@
+use std::borrow::Borrow;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use unicase::UniCase;
+
+use crate::CaseMode;
+
+/// A token in the trie, which hashes and compares according to its `CaseMode`.
+///
+/// All the keys of a processor have the same `CaseMode`, and the map is only queried with the
+/// borrowed form that matches it (`&SensitiveStr`, `&UnicaseStr` or `&AsciiCaseStr`), which
+/// hashes and compares just like the keys of that mode. That's what allows us to query the map
+/// with a token without allocating a `Key`.
+///
+/// There is no `Borrow<str>`, since a `str` doesn't hash like a case insensitive key.
+///
+/// The token is shared with all the other keys of the same token in the trie, see
+/// `Trie::key()`.
+#[derive(Clone)]
+pub(crate) struct Key {
+    token: Arc<str>,
+    case_mode: CaseMode,
+}
+
+impl Key {
+    pub fn new(token: Arc<str>, case_mode: CaseMode) -> Self {
+        Self { token, case_mode }
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.token
+    }
+
+    pub fn case_mode(&self) -> CaseMode {
+        self.case_mode
+    }
+}
+
+impl Hash for Key {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self.case_mode {
+            CaseMode::Sensitive => self.token.hash(state),
+            CaseMode::Insensitive => UnicaseStr::new(&self.token).hash(state),
+            CaseMode::AsciiInsensitive => AsciiCaseStr::new(&self.token).hash(state),
+        }
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        // the tokens of a trie are interned, so equal keys usually share the same string
+        if Arc::ptr_eq(&self.token, &other.token) {
+            return self.case_mode == other.case_mode;
+        }
+        match (self.case_mode, other.case_mode) {
+            (CaseMode::Sensitive, CaseMode::Sensitive) => self.token == other.token,
+            (CaseMode::Insensitive, CaseMode::Insensitive) => {
+                UnicaseStr::new(&self.token) == UnicaseStr::new(&other.token)
+            }
+            (CaseMode::AsciiInsensitive, CaseMode::AsciiInsensitive) => {
+                self.token.eq_ignore_ascii_case(&other.token)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Key {}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.token.fmt(f)
+    }
+}
+
+impl Borrow<SensitiveStr> for Key {
+    fn borrow(&self) -> &SensitiveStr {
+        SensitiveStr::new(&self.token)
+    }
+}
+
+impl Borrow<UnicaseStr> for Key {
+    fn borrow(&self) -> &UnicaseStr {
+        UnicaseStr::new(&self.token)
+    }
+}
+
+impl Borrow<AsciiCaseStr> for Key {
+    fn borrow(&self) -> &AsciiCaseStr {
+        AsciiCaseStr::new(&self.token)
+    }
+}
+
+/// A borrowed token that hashes and compares just like a `str`, to query the keys of a case
+/// sensitive processor.
+#[repr(transparent)]
+#[derive(Hash, PartialEq, Eq)]
+pub(crate) struct SensitiveStr(str);
+
+impl SensitiveStr {
+    pub fn new(s: &str) -> &Self {
+        // SAFETY: `SensitiveStr` is a `repr(transparent)` wrapper around `str`
+        unsafe { &*(s as *const str as *const Self) }
+    }
+}
+
+/// A borrowed token that hashes and compares just like `UniCase::unicode()`.
+///
+/// It must be `UniCase::unicode()` (which applies the full Unicode case folding, e.g.
+/// "ß" -> "ss"), because `UniCase::new()`/`into()` only folds ASCII strings as ASCII, and an
+/// ASCII key doesn't hash the same as a Unicode one, so "MASSE" and "Maße" would end up as two
+/// different keys.
+#[repr(transparent)]
+pub(crate) struct UnicaseStr(str);
+
+impl UnicaseStr {
+    pub fn new(s: &str) -> &Self {
+        // SAFETY: `UnicaseStr` is a `repr(transparent)` wrapper around `str`
+        unsafe { &*(s as *const str as *const Self) }
+    }
+}
+
+impl Hash for UnicaseStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        UniCase::unicode(&self.0).hash(state)
+    }
+}
+
+impl PartialEq for UnicaseStr {
+    fn eq(&self, other: &Self) -> bool {
+        UniCase::unicode(&self.0) == UniCase::unicode(&other.0)
+    }
+}
+
+impl Eq for UnicaseStr {}
+
+/// A borrowed token that hashes and compares regardless of the case of its ASCII letters.
+#[repr(transparent)]
+pub(crate) struct AsciiCaseStr(str);
+
+impl AsciiCaseStr {
+    pub fn new(s: &str) -> &Self {
+        // SAFETY: `AsciiCaseStr` is a `repr(transparent)` wrapper around `str`
+        unsafe { &*(s as *const str as *const Self) }
+    }
+}
+
+impl Hash for AsciiCaseStr {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for byte in self.0.bytes() {
+            state.write_u8(byte.to_ascii_lowercase());
+        }
+        // just like `str`, so that a token isn't hashed like its concatenation with another
+        state.write_u8(0xff);
+    }
+}
+
+impl PartialEq for AsciiCaseStr {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.eq_ignore_ascii_case(&other.0)
+    }
+}
+
+impl Eq for AsciiCaseStr {}
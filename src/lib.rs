@@ -1,39 +1,74 @@
 #![doc = include_str!("../README.md")]
 
+mod builder;
+mod children;
+mod codes;
+mod compiled;
+mod double_array;
+mod frozen;
+mod interner;
+mod key;
+mod mapped;
+mod mask;
+mod matches;
+#[cfg(feature = "memory-stats")]
+mod memory;
+mod processor;
+mod radix;
+mod template;
+pub mod tokenizer;
+mod trie;
+pub use builder::KeywordProcessorBuilder;
+pub use compiled::CompiledKeywordProcessor;
+pub use double_array::DoubleArrayKeywordProcessor;
+pub use frozen::FrozenKeywordProcessor;
+pub use interner::{Interner, Symbol};
+pub use mapped::{MappedError, MappedKeywordProcessor};
+pub use mask::Mask;
+pub use matches::{Edit, Match, MatchContext};
+#[cfg(feature = "memory-stats")]
+pub use memory::{MemoryStats, TrackingAllocator};
+pub use processor::{KeywordProcessor, Scanner};
+pub use radix::RadixKeywordProcessor;
+pub use template::{Template, TemplateError};
+pub use tokenizer::{
+    AsciiTokenizer, CharTokenizer, CjkChars, FoldDiacritics, GraphemeTokenizer, IgnorePunctuation,
+    Locale, LocaleCase, NonWordBoundaries, Normalization, Normalize, SeparateEmoji, SimpleTokenizer,
+    Stem, Tokenizer, WordBoundTokenizer, WordCharTokenizer,
+};
+
+type HashMap<V> = std::collections::HashMap<key::Key, V, fxhash::FxBuildHasher>;
+
+/// Whether the keywords are matched regardless of their case.
+///
+/// The case insensitive mode applies the full Unicode case folding (see [`unicase`]), so for
+/// example "Maße" matches "MASSE".
+///
+/// [`unicase`]: https://docs.rs/unicase/latest/unicase/
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CaseMode {
+    #[default]
+    Sensitive,
+    Insensitive,
+    /// Same as `Insensitive`, but only the ASCII letters are folded (byte by byte), which is
+    /// faster when the keywords and the texts are known to be ASCII (e.g. English).
+    AsciiInsensitive,
+}
+
+/// A [`KeywordProcessor`] that is case sensitive, kept for compatibility.
 #[path = "."]
 pub mod case_sensitive {
-    type HashMap<'a, Node> = std::collections::HashMap<&'a str, Node, fxhash::FxBuildHasher>;
-    mod shared;
-    pub use shared::KeywordProcessor;
+    const CASE_MODE: crate::CaseMode = crate::CaseMode::Sensitive;
+    #[allow(clippy::duplicate_mod)]
+    mod compat;
+    pub use compat::KeywordProcessor;
 }
 
+/// A [`KeywordProcessor`] that is case insensitive, kept for compatibility.
 #[path = "."]
 pub mod case_insensitive {
-    use std::collections::hash_map::Entry;
-    use unicase::UniCase;
-
-    #[derive(Debug, Default, PartialEq)]
-    struct UnicaseHashMap<'a, V> {
-        inner: std::collections::HashMap<UniCase<&'a str>, V, fxhash::FxBuildHasher>,
-    }
-
-    impl<'a, V> UnicaseHashMap<'a, V>
-    {
-        pub fn entry(&mut self, k: &'a str) -> Entry<UniCase<&'a str>, V> {
-            // TODO: make sure its not doing the ASCII check
-            // TODO: benchmark `into() vs Unicase::unicode()`
-            self.inner.entry(UniCase::unicode(k))
-        }
-
-        pub fn get(&self, k: &'a str) -> Option<&V> {
-            self.inner.get(&UniCase::unicode(k))
-        }
-    }
-
-    type HashMap<'a, Node> = UnicaseHashMap<'a, Node>;
-    mod shared;
-    pub use shared::KeywordProcessor;
+    const CASE_MODE: crate::CaseMode = crate::CaseMode::Insensitive;
+    #[allow(clippy::duplicate_mod)]
+    mod compat;
+    pub use compat::KeywordProcessor;
 }
-
-// TODO: add performance benchmarks using criterion
-// TODO: monitor memory usage while running tests (crate `memory_statsCopy item path`)
@@ -0,0 +1,252 @@
+use std::fmt;
+use crate::radix::{RadixArrays, RadixExtractor, RadixNode};
+use crate::{CaseMode, Match, Tokenizer, WordBoundTokenizer};
+
+pub(crate) const MAGIC: [u8; 4] = *b"FT2R";
+pub(crate) const VERSION: u32 = 1;
+/// The number of `u32` in the header (including the magic).
+const HEADER_LEN: usize = 9;
+/// The number of `u32` in a node.
+const NODE_LEN: usize = 6;
+/// The number of `u32` in a table of children.
+const TABLE_LEN: usize = 256;
+
+/// A [`RadixKeywordProcessor`](crate::RadixKeywordProcessor) that is read directly from the
+/// buffer written by `RadixKeywordProcessor::to_bytes()`, without deserializing it (e.g. from a
+/// memory-mapped file), so it's ready as soon as the buffer is.
+///
+/// The buffer only holds offsets into itself, so it can be mapped at any address, and it
+/// doesn't need to be aligned. It's checked when the processor is created (its header, the
+/// length of its sections and that its values are UTF-8), but not every index in it, so a
+/// corrupted buffer may make the extraction panic.
+///
+/// The tokenizer isn't part of the buffer, it has to be the same as the one the keywords were
+/// added with.
+///
+/// ```
+/// use flashtext2::{KeywordProcessor, MappedKeywordProcessor};
+///
+/// let mut kp = KeywordProcessor::new();
+/// kp.add_keywords_from_iter(["New York", "New York City", "York"]);
+/// let bytes = kp.to_radix_tree().to_bytes();
+/// // e.g. written to a file, and memory-mapped later
+/// let mapped = MappedKeywordProcessor::new(&bytes).unwrap();
+///
+/// let text = "New York City and York";
+/// assert_eq!(mapped.extract_keywords(text).collect::<Vec<_>>(), ["New York City", "York"]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct MappedKeywordProcessor<'a, T = WordBoundTokenizer> {
+    nodes: &'a [u8],
+    child_ids: &'a [u8],
+    tables: &'a [u8],
+    // the start of each value in `values`, and the end of the last one
+    value_offsets: &'a [u8],
+    labels: &'a [u8],
+    child_bytes: &'a [u8],
+    values: &'a str,
+    tokenizer: T,
+    case_mode: CaseMode,
+}
+
+impl<'a> MappedKeywordProcessor<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self, MappedError> {
+        Self::with_tokenizer(bytes, WordBoundTokenizer)
+    }
+}
+
+impl<'a, T: Tokenizer> MappedKeywordProcessor<'a, T> {
+    /// Reads the processor from `bytes`, whose keywords were split with `tokenizer`.
+    pub fn with_tokenizer(bytes: &'a [u8], tokenizer: T) -> Result<Self, MappedError> {
+        if bytes.len() < HEADER_LEN * 4 {
+            return Err(MappedError::Truncated);
+        }
+        if bytes[..4] != MAGIC {
+            return Err(MappedError::InvalidMagic);
+        }
+        let header = |idx| read_u32(bytes, idx);
+        if header(1) != VERSION {
+            return Err(MappedError::UnsupportedVersion(header(1)));
+        }
+        let case_mode = match header(2) {
+            0 => CaseMode::Sensitive,
+            1 => CaseMode::Insensitive,
+            2 => CaseMode::AsciiInsensitive,
+            code => return Err(MappedError::InvalidCaseMode(code)),
+        };
+        let [node_count, child_count, table_count, value_count, labels_len, values_len] =
+            [3, 4, 5, 6, 7, 8].map(|idx| header(idx) as usize);
+
+        let mut rest = &bytes[HEADER_LEN * 4..];
+        // the number of items in the section, and the size of each one
+        let mut section = |count: usize, size: usize| {
+            let len = count.checked_mul(size).ok_or(MappedError::Truncated)?;
+            if rest.len() < len {
+                return Err(MappedError::Truncated);
+            }
+            let (section, tail) = rest.split_at(len);
+            rest = tail;
+            Ok(section)
+        };
+        let nodes = section(node_count, NODE_LEN * 4)?;
+        let child_ids = section(child_count, 4)?;
+        let tables = section(table_count, TABLE_LEN * 4)?;
+        let value_offsets = section(value_count + 1, 4)?;
+        let labels = section(labels_len, 1)?;
+        let child_bytes = section(child_count, 1)?;
+        let values = section(values_len, 1)?;
+        if !rest.is_empty() {
+            return Err(MappedError::TrailingBytes);
+        }
+        if node_count == 0 {
+            return Err(MappedError::Truncated);
+        }
+        let values = std::str::from_utf8(values).map_err(|_| MappedError::InvalidUtf8)?;
+        Ok(Self {
+            nodes,
+            child_ids,
+            tables,
+            value_offsets,
+            labels,
+            child_bytes,
+            values,
+            tokenizer,
+            case_mode,
+        })
+    }
+
+    pub fn tokenizer(&self) -> &T {
+        &self.tokenizer
+    }
+
+    pub fn case_mode(&self) -> CaseMode {
+        self.case_mode
+    }
+
+    pub fn len(&self) -> usize {
+        self.value_offsets.len() / 4 - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the `clean_word` of the value at `idx`.
+    ///
+    /// # Panics
+    ///
+    /// If the offsets of the value are out of bounds, or not on a char boundary.
+    fn value(&self, idx: u32) -> &'a str {
+        let start = read_u32(self.value_offsets, idx as usize) as usize;
+        let end = read_u32(self.value_offsets, idx as usize + 1) as usize;
+        &self.values[start..end]
+    }
+
+    pub fn contains<S: AsRef<str> + ?Sized>(&self, word: &S) -> bool {
+        self.get_clean_word(word).is_some()
+    }
+
+    pub fn get_clean_word<S: AsRef<str> + ?Sized>(&self, word: &S) -> Option<&'a str> {
+        let value_idx = self.lookup(&self.tokenizer, self.case_mode, word.as_ref())?;
+        Some(self.value(value_idx))
+    }
+
+    pub fn extract_keywords<'b>(&'b self, text: &'b str) -> impl Iterator<Item = &'a str> + 'b {
+        RadixExtractor::new(self, &self.tokenizer, self.case_mode, text)
+            .map(|(value_idx, _, _)| self.value(value_idx))
+    }
+
+    pub fn extract_keywords_with_span<'b>(
+        &'b self,
+        text: &'b str,
+    ) -> impl Iterator<Item = Match<'b>> + 'b {
+        RadixExtractor::new(self, &self.tokenizer, self.case_mode, text)
+            .map(|(value_idx, start, end)| {
+                Match::new(&text[start..end], self.value(value_idx), start, end)
+            })
+    }
+}
+
+impl<T> RadixArrays for MappedKeywordProcessor<'_, T> {
+    #[inline]
+    fn node(&self, id: u32) -> RadixNode {
+        let idx = id as usize * NODE_LEN;
+        let field = |offset| read_u32(self.nodes, idx + offset);
+        RadixNode {
+            label_start: field(0),
+            label_len: field(1),
+            value_idx: field(2),
+            children_start: field(3),
+            children_len: field(4),
+            table: field(5),
+        }
+    }
+
+    #[inline]
+    fn label(&self, idx: u32) -> u8 {
+        self.labels[idx as usize]
+    }
+
+    #[inline]
+    fn child_bytes(&self, start: u32, len: u32) -> &[u8] {
+        &self.child_bytes[start as usize..(start + len) as usize]
+    }
+
+    #[inline]
+    fn child_id(&self, idx: u32) -> u32 {
+        read_u32(self.child_ids, idx as usize)
+    }
+
+    #[inline]
+    fn table_child(&self, table: u32, byte: u8) -> u32 {
+        read_u32(self.tables, table as usize * TABLE_LEN + byte as usize)
+    }
+}
+
+/// Reads the `idx`-th little-endian `u32` of `bytes`.
+#[inline]
+fn read_u32(bytes: &[u8], idx: usize) -> u32 {
+    let bytes = &bytes[idx * 4..idx * 4 + 4];
+    u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Returns the code of `case_mode` in the header.
+pub(crate) fn case_mode_code(case_mode: CaseMode) -> u32 {
+    match case_mode {
+        CaseMode::Sensitive => 0,
+        CaseMode::Insensitive => 1,
+        CaseMode::AsciiInsensitive => 2,
+    }
+}
+
+/// The reason a [`MappedKeywordProcessor`] couldn't be read from a buffer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MappedError {
+    /// The buffer doesn't start with the magic bytes of the format.
+    InvalidMagic,
+    /// The buffer was written by another version of the format.
+    UnsupportedVersion(u32),
+    /// The code of the `CaseMode` is unknown.
+    InvalidCaseMode(u32),
+    /// The buffer is shorter than its header says.
+    Truncated,
+    /// The buffer is longer than its header says.
+    TrailingBytes,
+    /// The values aren't valid UTF-8.
+    InvalidUtf8,
+}
+
+impl fmt::Display for MappedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidMagic => write!(f, "invalid magic bytes"),
+            Self::UnsupportedVersion(version) => write!(f, "unsupported version {version}"),
+            Self::InvalidCaseMode(code) => write!(f, "invalid case mode {code}"),
+            Self::Truncated => write!(f, "truncated buffer"),
+            Self::TrailingBytes => write!(f, "trailing bytes after the buffer"),
+            Self::InvalidUtf8 => write!(f, "the values aren't valid UTF-8"),
+        }
+    }
+}
+
+impl std::error::Error for MappedError {}
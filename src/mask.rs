@@ -0,0 +1,45 @@
+/// What the keywords are replaced with by `KeywordProcessor::redact_keywords()`.
+///
+/// It can be created from a `&str` (a fixed mask) or a `char` (repeated for each char of the
+/// keyword).
+///
+/// ```
+/// use flashtext2::KeywordProcessor;
+///
+/// let mut kp = KeywordProcessor::new();
+/// kp.add_keyword("555-0100");
+///
+/// let text = "Call 555-0100 now";
+/// assert_eq!(kp.redact_keywords(text, "[REDACTED]"), "Call [REDACTED] now");
+/// assert_eq!(kp.redact_keywords(text, '*'), "Call ******** now");
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mask<'a> {
+    /// The same string for every keyword.
+    Fixed(&'a str),
+    /// The char repeated as many times as there are chars in the matched text, so the text
+    /// keeps its length (in chars).
+    Repeat(char),
+}
+
+impl Mask<'_> {
+    /// Writes the mask of `matched` (the text of a keyword) to `string`.
+    pub(crate) fn push_to(&self, matched: &str, string: &mut String) {
+        match *self {
+            Self::Fixed(mask) => string.push_str(mask),
+            Self::Repeat(c) => string.extend(matched.chars().map(|_| c)),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for Mask<'a> {
+    fn from(mask: &'a str) -> Self {
+        Self::Fixed(mask)
+    }
+}
+
+impl From<char> for Mask<'_> {
+    fn from(c: char) -> Self {
+        Self::Repeat(c)
+    }
+}
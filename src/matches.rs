@@ -0,0 +1,119 @@
+use std::borrow::Cow;
+use std::ops::Range;
+
+/// A keyword found in a text.
+///
+/// The span is in bytes (unless stated otherwise), so the part of the text that was matched
+/// (`keyword`) is also `&text[m.start..m.end]`, see `text()`.
+///
+/// More fields may be added in the future, so it can't be created outside of this crate.
+///
+/// ```
+/// use flashtext2::case_insensitive::KeywordProcessor;
+///
+/// let mut kp = KeywordProcessor::new();
+/// kp.add_keyword_with_clean_word("rust", "Rust");
+///
+/// let text = "I love RUST!";
+/// let m = kp.extract_keywords_with_span(text).next().unwrap();
+/// assert_eq!((&*m.keyword, m.clean_word, m.range()), ("RUST", "Rust", 7..11));
+/// assert_eq!(m.text(text), "RUST");
+/// assert_eq!(m, ("Rust", 7, 11));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Match<'a> {
+    /// The part of the text that was matched (e.g. in its original case), which is only owned
+    /// when the text isn't borrowed (e.g. when it's read from a stream).
+    pub keyword: Cow<'a, str>,
+    /// The `clean_word` of the keyword.
+    pub clean_word: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+impl<'a> Match<'a> {
+    pub(crate) fn new(
+        keyword: impl Into<Cow<'a, str>>,
+        clean_word: &'a str,
+        start: usize,
+        end: usize,
+    ) -> Self {
+        Self { keyword: keyword.into(), clean_word, start, end }
+    }
+
+    /// Returns the span of the match, e.g. to slice the text.
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// Returns the part of `source` (the text the keyword was extracted from) that was matched.
+    ///
+    /// # Panics
+    ///
+    /// If the span isn't in `source` (e.g. if it isn't in bytes).
+    pub fn text<'t>(&self, source: &'t str) -> &'t str {
+        &source[self.range()]
+    }
+}
+
+/// The matches used to be `(clean_word, start, end)` tuples, so they can still be compared.
+impl PartialEq<(&str, usize, usize)> for Match<'_> {
+    fn eq(&self, &(clean_word, start, end): &(&str, usize, usize)) -> bool {
+        self.clean_word == clean_word && self.start == start && self.end == end
+    }
+}
+
+impl<'a> From<Match<'a>> for (&'a str, usize, usize) {
+    fn from(m: Match<'a>) -> Self {
+        (m.clean_word, m.start, m.end)
+    }
+}
+
+/// A keyword found in a text, with the tokens of the text around it (e.g. to show a snippet
+/// of where it was found), see `KeywordProcessor::extract_keywords_with_context()`.
+///
+/// ```
+/// use flashtext2::KeywordProcessor;
+///
+/// let mut kp = KeywordProcessor::new();
+/// kp.add_keyword("Rust");
+///
+/// let context = kp.extract_keywords_with_context("I love Rust so much", 2).next().unwrap();
+/// assert_eq!((context.left, &*context.matched.keyword, context.right), ("love ", "Rust", " so"));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct MatchContext<'a> {
+    /// The keyword, with its span in bytes.
+    pub matched: Match<'a>,
+    /// The (up to) `n_tokens` tokens of the text before the keyword.
+    pub left: &'a str,
+    /// The (up to) `n_tokens` tokens of the text after the keyword.
+    pub right: &'a str,
+}
+
+/// A keyword that was replaced in a text, with its span in the original text and in the new
+/// one, e.g. to move the annotations of the original text onto the new one.
+///
+/// ```
+/// use flashtext2::KeywordProcessor;
+///
+/// let mut kp = KeywordProcessor::new();
+/// kp.add_keyword_with_clean_word("py", "Python");
+///
+/// let (replaced, edits) = kp.replace_keywords_with_edits("I love py!");
+/// assert_eq!(replaced, "I love Python!");
+/// assert_eq!((edits[0].clean_word, edits[0].original.clone()), ("Python", 7..9));
+/// assert_eq!(&replaced[edits[0].replaced.clone()], "Python");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub struct Edit<'a> {
+    /// The `clean_word` of the keyword.
+    pub clean_word: &'a str,
+    /// The span (in bytes) of the keyword in the original text.
+    pub original: Range<usize>,
+    /// The span (in bytes) of its replacement in the new text.
+    pub replaced: Range<usize>,
+}
@@ -0,0 +1,105 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+// the heap memory currently allocated through `TrackingAllocator`, and the most it reached
+// since `reset_peak()`
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+/// A global allocator that keeps track of the heap memory allocated by the process, so the
+/// `KeywordProcessor` can record how much memory was needed to add the keywords, see
+/// `KeywordProcessor::memory_stats()`.
+///
+/// ```
+/// use flashtext2::TrackingAllocator;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: TrackingAllocator = TrackingAllocator::new(std::alloc::System);
+/// ```
+#[derive(Debug, Default)]
+pub struct TrackingAllocator<A = System>(A);
+
+impl<A> TrackingAllocator<A> {
+    /// Wraps the allocator that actually allocates the memory.
+    pub const fn new(allocator: A) -> Self {
+        Self(allocator)
+    }
+}
+
+// SAFETY: the calls are forwarded to the wrapped allocator, and only counted
+unsafe impl<A: GlobalAlloc> GlobalAlloc for TrackingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.0.alloc(layout);
+        if !ptr.is_null() {
+            grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = self.0.alloc_zeroed(layout);
+        if !ptr.is_null() {
+            grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_ptr = self.0.realloc(ptr, layout, new_size);
+        if !new_ptr.is_null() {
+            if new_size > layout.size() {
+                grow(new_size - layout.size());
+            } else {
+                CURRENT.fetch_sub(layout.size() - new_size, Ordering::Relaxed);
+            }
+        }
+        new_ptr
+    }
+}
+
+fn grow(size: usize) {
+    let current = CURRENT.fetch_add(size, Ordering::Relaxed) + size;
+    PEAK.fetch_max(current, Ordering::Relaxed);
+}
+
+/// The heap memory (in bytes) used by the process while the keywords were added to a
+/// `KeywordProcessor`, as counted by the [`TrackingAllocator`].
+///
+/// The allocations of every thread are counted, so keywords added to several processors at
+/// once (on different threads) end up in the stats of all of them.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryStats {
+    /// The heap memory that was used before the keywords were first added.
+    pub start: usize,
+    /// The most heap memory that was used while the keywords were added.
+    pub peak: usize,
+}
+
+impl MemoryStats {
+    /// Returns how much more memory the process needed while the keywords were added (which
+    /// includes the memory that was only used temporarily, e.g. to resize the hashmaps).
+    pub fn peak_increase(&self) -> usize {
+        self.peak.saturating_sub(self.start)
+    }
+}
+
+/// Starts a measure, returns the heap memory that is currently used, or `None` if the
+/// `TrackingAllocator` isn't the global allocator.
+pub(crate) fn start() -> Option<usize> {
+    let current = CURRENT.load(Ordering::Relaxed);
+    // nothing was ever counted, unless the process didn't allocate anything yet
+    (current != 0).then(|| {
+        PEAK.store(current, Ordering::Relaxed);
+        current
+    })
+}
+
+/// Returns the most heap memory that was used since `start()`.
+pub(crate) fn peak() -> usize {
+    PEAK.load(Ordering::Relaxed)
+}
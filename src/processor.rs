@@ -0,0 +1,2498 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io::{self, BufRead};
+use std::ops::ControlFlow;
+use unicase::UniCase;
+use unicode_segmentation::UnicodeSegmentation;
+use crate::children;
+use crate::key::{Key, UnicaseStr};
+use crate::radix::fold;
+use crate::trie::{Node, NodeId, NodeRef, Trie, ROOT};
+use crate::{
+    CaseMode, CompiledKeywordProcessor, DoubleArrayKeywordProcessor, Edit, FrozenKeywordProcessor,
+    KeywordProcessorBuilder, Mask, Match, MatchContext, RadixKeywordProcessor, Template, Tokenizer,
+    WordBoundTokenizer,
+};
+
+impl<'a, V> NodeRef<'a, V> {
+    /// Returns the child of the token (of a text or a keyword), which is normalized first.
+    #[inline]
+    fn child<T: Tokenizer>(self, token: &str, matcher: Matcher<'_, T>) -> Option<Self> {
+        self.key_child(&matcher.tokenizer.normalize(token), matcher.case_mode)
+    }
+
+    /// Same as `child()`, but also returns the key of the child, i.e. the normalized token it
+    /// was inserted with.
+    fn child_with_key<T: Tokenizer>(
+        self,
+        token: &str,
+        matcher: Matcher<'_, T>,
+    ) -> Option<(&'a Key, Self)> {
+        self.key_child_with_key(&matcher.tokenizer.normalize(token), matcher.case_mode)
+    }
+}
+
+impl<V> Trie<V> {
+    /// Returns the node at the end of the `tokens` path (inserting the missing nodes), and the
+    /// number of tokens.
+    fn get_or_insert<'t, T: Tokenizer>(
+        &mut self,
+        tokens: impl Iterator<Item = (usize, &'t str)>,
+        matcher: Matcher<'_, T>,
+    ) -> (&mut Node<V>, usize) {
+        let mut id = ROOT;
+        let mut token_count = 0;
+        for (_, token) in tokens {
+            let token = matcher.tokenizer.normalize(token);
+            id = match self.node(id).children.get(&token, matcher.case_mode) {
+                Some((_, child)) => child,
+                None => {
+                    let key = self.key(&token, matcher.case_mode);
+                    self.child_or_insert(id, key)
+                }
+            };
+            token_count += 1;
+        }
+        (self.node_mut(id), token_count)
+    }
+
+    /// Inserts the `keywords` (their normalized tokens and their value), which are sorted by
+    /// their tokens, one level of the trie at a time, returns the number of new keywords.
+    ///
+    /// The keywords that share their first tokens are next to each other, so the node of each
+    /// of these tokens is only looked up (or inserted) once for all of them.
+    fn insert_sorted(
+        &mut self,
+        keywords: &mut [(Vec<String>, Option<V>)],
+        case_mode: CaseMode,
+        token_counts: &mut TokenCounts,
+    ) -> usize {
+        let mut added = 0;
+        // the nodes that are `depth` tokens deep, with the range of the keywords under them
+        let mut level = vec![(ROOT, 0..keywords.len())];
+        let mut depth = 0;
+        while !level.is_empty() {
+            let mut next_level = Vec::new();
+            for (id, range) in level {
+                let mut start = range.start;
+                // the keywords that end at the node are a prefix of the others, so they come
+                // first (in the order they were added, so the last one wins)
+                while start < range.end && keywords[start].0.len() == depth {
+                    let value = keywords[start].1.take().expect("each keyword is inserted once");
+                    if self.node_mut(id).set_value(value) {
+                        token_counts.add(depth);
+                        added += 1;
+                    }
+                    start += 1;
+                }
+                while start < range.end {
+                    let token = &keywords[start].0[depth];
+                    let len = keywords[start..range.end].partition_point(|(tokens, _)| {
+                        children::eq(&tokens[depth], token, case_mode)
+                    });
+                    let key = self.key(token, case_mode);
+                    next_level.push((self.child_or_insert(id, key), start..start + len));
+                    start += len;
+                }
+            }
+            level = next_level;
+            depth += 1;
+        }
+        added
+    }
+
+    /// Copies the nodes of `other` into the node `id` (which is `depth` tokens deep), returns
+    /// the number of new keywords.
+    ///
+    /// The tokens of `other` are inserted with `case_mode`, which may not be the one they
+    /// were added with.
+    fn merge(
+        &mut self,
+        id: NodeId,
+        other: NodeRef<'_, V>,
+        case_mode: CaseMode,
+        depth: usize,
+        token_counts: &mut TokenCounts,
+    ) -> usize
+    where
+        V: Clone,
+    {
+        let mut added = 0;
+        let node = self.node_mut(id);
+        if let Some(value) = other.value() {
+            if node.set_value(value.clone()) {
+                token_counts.add(depth);
+                added += 1;
+            }
+            // only the keywords of `other` are overwritten, not the nodes on their path
+            node.priority = other.priority();
+        }
+        for (token, other_child) in other.children() {
+            let key = self.key(token.as_str(), case_mode);
+            let child = self.child_or_insert(id, key);
+            added += self.merge(child, other_child, case_mode, depth + 1, token_counts);
+        }
+        added
+    }
+
+    /// Clears the value at the end of the `tokens` path from the node `id` (the tokens are
+    /// normalized), and prunes the nodes that were only there to lead to it.
+    fn remove(&mut self, id: NodeId, tokens: &[Cow<'_, str>], case_mode: CaseMode) -> bool {
+        let Some((token, rest)) = tokens.split_first() else {
+            return self.node_mut(id).value.take().is_some();
+        };
+        let Some(child) = self.child_id(id, token, case_mode) else {
+            return false;
+        };
+        let removed = self.remove(child, rest, case_mode);
+        // the pruning goes up the path until the first node that is still in use
+        let child_node = self.node(child);
+        if removed && child_node.value.is_none() && child_node.children.is_empty() {
+            self.remove_child(id, token, case_mode);
+        }
+        removed
+    }
+
+    /// Clears the values of all the keywords of `words` (from the node `id`), and prunes the
+    /// nodes that were only there to lead to them, returns the number of keywords that were
+    /// removed.
+    fn remove_all(
+        &mut self,
+        id: NodeId,
+        words: NodeRef<'_, ()>,
+        case_mode: CaseMode,
+        depth: usize,
+        token_counts: &mut TokenCounts,
+    ) -> usize {
+        let mut removed = 0;
+        if words.value().is_some() && self.node_mut(id).value.take().is_some() {
+            token_counts.remove(depth);
+            removed += 1;
+        }
+        for (token, words_child) in words.children() {
+            let Some(child) = self.child_id(id, token.as_str(), case_mode) else {
+                continue;
+            };
+            let child_removed =
+                self.remove_all(child, words_child, case_mode, depth + 1, token_counts);
+            let child_node = self.node(child);
+            if child_removed > 0 && child_node.value.is_none() && child_node.children.is_empty() {
+                self.remove_child(id, token.as_str(), case_mode);
+            }
+            removed += child_removed;
+        }
+        removed
+    }
+}
+
+/// How the tokens are compared with the keys of the trie: they are normalized by the
+/// tokenizer (just like the keywords were), and then compared according to the `CaseMode`.
+struct Matcher<'a, T> {
+    tokenizer: &'a T,
+    case_mode: CaseMode,
+}
+
+impl<'a, T> Matcher<'a, T> {
+    fn new(tokenizer: &'a T, case_mode: CaseMode) -> Self {
+        Self { tokenizer, case_mode }
+    }
+}
+
+// not derived, because that would require `T: Copy`
+impl<T> Clone for Matcher<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Matcher<'_, T> {}
+
+/// The number of keywords with each number of tokens, so we know how long the longest one is
+/// without walking the trie.
+#[derive(Clone, Default, PartialEq, Debug)]
+struct TokenCounts(Vec<usize>);
+
+impl TokenCounts {
+    fn add(&mut self, token_count: usize) {
+        if self.0.len() <= token_count {
+            self.0.resize(token_count + 1, 0);
+        }
+        self.0[token_count] += 1;
+    }
+
+    fn remove(&mut self, token_count: usize) {
+        self.0[token_count] -= 1;
+        while self.0.last() == Some(&0) {
+            self.0.pop();
+        }
+    }
+
+    fn max(&self) -> usize {
+        self.0.len().saturating_sub(1)
+    }
+}
+
+/// Extracts and replaces keywords in a text.
+///
+/// Each keyword carries a value of type `V`, which defaults to `String` (the `clean_word`
+/// the keyword is extracted/replaced with), but can be anything, e.g. the ID of a record:
+/// use `KeywordProcessor::<V>::default()` and `add_keyword_with_value()`.
+///
+/// The processor is case sensitive, unless it's created with `CaseMode::Insensitive`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct KeywordProcessor<V = String, T = WordBoundTokenizer> {
+    trie: Trie<V>,
+    len: usize, // the number of keywords the struct contains (not the number of nodes)
+    token_counts: TokenCounts,
+    tokenizer: T,
+    case_mode: CaseMode,
+    // whether a keyword was ever added with a priority, otherwise the extraction doesn't need
+    // to look for the keywords that overlap with each match
+    prioritized: bool,
+    // the keywords that are replaced with something else than their value (see
+    // `add_keyword_with_replacement()`), which are rarely used, so they don't weigh on the
+    // nodes of the main trie
+    replacements: Trie<String>,
+    // the heap memory used while the keywords were added, see `memory_stats()`
+    #[cfg(feature = "memory-stats")]
+    memory_stats: Option<crate::MemoryStats>,
+}
+
+impl<V, T: Default> Default for KeywordProcessor<V, T> {
+    fn default() -> Self {
+        Self::from_parts(CaseMode::default(), 0, T::default())
+    }
+}
+
+impl KeywordProcessor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty processor with room for `capacity` distinct first tokens (i.e. the
+    /// children of the root of the trie) without reallocating.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_parts(CaseMode::default(), capacity, WordBoundTokenizer)
+    }
+
+    /// Creates an empty processor that matches the keywords according to `case_mode`.
+    pub fn with_case_mode(case_mode: CaseMode) -> Self {
+        Self::from_parts(case_mode, 0, WordBoundTokenizer)
+    }
+
+    /// Returns a builder to configure the processor (e.g. its `CaseMode` and tokenizer).
+    pub fn builder() -> KeywordProcessorBuilder {
+        KeywordProcessorBuilder::new()
+    }
+
+    /// Creates a processor with the keywords of `iter`, which are sorted and then inserted all
+    /// at once, see `add_sorted_keywords_with_value()`.
+    ///
+    /// ```
+    /// use flashtext2::KeywordProcessor;
+    ///
+    /// let keywords = ["New York", "Rust", "New Delhi", "New York City"];
+    /// let kp = KeywordProcessor::from_sorted_keywords(keywords);
+    /// assert_eq!(kp.len(), 4);
+    /// assert_eq!(kp.extract_keywords("New York City").collect::<Vec<_>>(), ["New York City"]);
+    /// ```
+    pub fn from_sorted_keywords<I>(iter: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut kp = Self::new();
+        kp.add_sorted_keywords_with_value(iter.into_iter().map(|word| {
+            let clean_word = word.as_ref().to_string();
+            (word, clean_word)
+        }));
+        kp
+    }
+}
+
+impl<V, T> KeywordProcessor<V, T> {
+    pub(crate) fn from_parts(case_mode: CaseMode, capacity: usize, tokenizer: T) -> Self {
+        Self {
+            trie: Trie::with_capacity(capacity),
+            len: 0,
+            token_counts: TokenCounts::default(),
+            tokenizer,
+            case_mode,
+            prioritized: false,
+            replacements: Trie::default(),
+            #[cfg(feature = "memory-stats")]
+            memory_stats: None,
+        }
+    }
+}
+
+impl<V, T: Tokenizer> KeywordProcessor<V, T> {
+    /// Creates an empty processor that splits the keywords and the text with `tokenizer`.
+    pub fn with_tokenizer(tokenizer: T) -> Self {
+        Self::from_parts(CaseMode::default(), 0, tokenizer)
+    }
+
+    pub fn tokenizer(&self) -> &T {
+        &self.tokenizer
+    }
+
+    pub fn case_mode(&self) -> CaseMode {
+        self.case_mode
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        // or `!self.trie.root().has_children()`
+        self.len == 0
+    }
+
+    /// Returns the number of tokens of the longest keyword (or `0` if there are none).
+    ///
+    /// E.g. when the text is fed in chunks, this is how many tokens of overlap to keep between
+    /// them so that no keyword is split.
+    pub fn max_keyword_tokens(&self) -> usize {
+        self.token_counts.max()
+    }
+
+    fn matcher(&self) -> Matcher<'_, T> {
+        Matcher::new(&self.tokenizer, self.case_mode)
+    }
+
+    fn count_keyword(&mut self, token_count: usize) {
+        self.len += 1;
+        self.token_counts.add(token_count);
+    }
+
+    // we want to keep the implementation of the trie private, because it will probably change
+    // in the future, so it's only exposed to the other representations of the processor
+    pub(crate) fn trie(&self) -> NodeRef<'_, V> {
+        self.trie.root()
+    }
+
+    /// Splits the processor into its trie and its tokenizer, for the representations that
+    /// consume it.
+    pub(crate) fn into_parts(self) -> (Trie<V>, T) {
+        (self.trie, self.tokenizer)
+    }
+
+    /// Adds `word` as a keyword that carries `value`, if `word` is already a keyword its value
+    /// is overwritten.
+    #[inline]
+    pub fn add_keyword_with_value(&mut self, word: &str, value: V) {
+        // the tokens are copied into the trie, so `word` doesn't need to outlive the processor
+        let matcher = Matcher::new(&self.tokenizer, self.case_mode);
+        let (node, token_count) =
+            self.trie.get_or_insert(self.tokenizer.tokenize(word), matcher);
+        if node.set_value(value) {
+            self.count_keyword(token_count);
+        }
+    }
+
+    /// Adds the `(keyword, value)` pairs of `iter`, like `add_keyword_with_value()`, but all at
+    /// once: the keywords are sorted by their tokens, then the trie is built one level at a
+    /// time, so the tokens that they share (e.g. "New" in "New York" and "New Delhi") are only
+    /// looked up once, instead of once per keyword.
+    ///
+    /// The keywords can come in any order, and if one is there more than once, its last value
+    /// is kept.
+    pub fn add_sorted_keywords_with_value<I, W>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (W, V)>,
+        W: AsRef<str>,
+    {
+        self.measured(|kp| {
+            let (tokenizer, case_mode) = (&kp.tokenizer, kp.case_mode);
+            let mut keywords: Vec<_> = iter
+                .into_iter()
+                .map(|(word, value)| {
+                    let tokens = tokenizer.tokenize(word.as_ref());
+                    let tokens = tokens.map(|(_, token)| tokenizer.normalize(token).into_owned());
+                    (tokens.collect::<Vec<_>>(), Some(value))
+                })
+                .collect();
+            // the sort is stable, so the keywords that are the same (e.g. in another case) stay
+            // in the order they were added in
+            keywords.sort_by_cached_key(|(tokens, _)| {
+                let folded = tokens.iter().map(|token| fold(Cow::Borrowed(token), case_mode));
+                folded.map(Cow::into_owned).collect::<Vec<_>>()
+            });
+            kp.len += kp.trie.insert_sorted(&mut keywords, case_mode, &mut kp.token_counts);
+        });
+    }
+
+    /// Adds `word` as a keyword that carries `value`, and that wins over the keywords with a
+    /// lower priority (all the others have a priority of `0`).
+    ///
+    /// Without priorities, the keywords are extracted leftmost-longest: the longest keyword
+    /// that starts at the first token, and so on after it. With them, the keyword with the
+    /// highest priority that starts at a token is picked (and then the longest one), unless
+    /// a keyword that starts inside it has an even higher priority, in which case the
+    /// extraction falls back to a keyword that ends before that one (if there is any).
+    ///
+    /// The priorities are ignored by `extract_keywords_all()` (that returns all the keywords
+    /// anyway) and `extract_keywords_from_reader()`.
+    pub fn add_keyword_with_priority(&mut self, word: &str, value: V, priority: u32) {
+        let matcher = Matcher::new(&self.tokenizer, self.case_mode);
+        let (node, token_count) =
+            self.trie.get_or_insert(self.tokenizer.tokenize(word), matcher);
+        let added = node.set_value(value);
+        node.priority = priority;
+        if added {
+            self.count_keyword(token_count);
+        }
+        self.prioritized |= priority != 0;
+    }
+
+    /// Adds all the keywords of `other` to this processor.
+    ///
+    /// Just like adding them one by one, if a keyword is in both processors, its value is
+    /// overwritten with the one from `other`, and the keywords follow the `CaseMode` of this
+    /// processor.
+    /// The tries are merged directly, so both processors should split the tokens the same way.
+    pub fn merge(&mut self, other: &Self)
+    where
+        V: Clone,
+    {
+        let (trie, case_mode) = (other.trie.root(), self.case_mode);
+        self.len += self.trie.merge(ROOT, trie, case_mode, 0, &mut self.token_counts);
+        self.prioritized |= other.prioritized;
+        let mut token_counts = TokenCounts::default();
+        let replacements = other.replacements.root();
+        self.replacements.merge(ROOT, replacements, case_mode, 0, &mut token_counts);
+    }
+
+    /// Removes all the keywords, but keeps the capacity of the root of the trie, so the
+    /// processor can be reused (e.g. to reload the keywords).
+    pub fn clear(&mut self) {
+        self.trie.clear();
+        self.len = 0;
+        self.token_counts = TokenCounts::default();
+        self.prioritized = false;
+        self.replacements = Trie::default();
+    }
+
+    /// Returns the number of nodes in the trie (not counting the root), i.e. the number of
+    /// distinct token sequences that are a keyword or a prefix of one.
+    pub fn node_count(&self) -> usize {
+        self.trie.node_count()
+    }
+
+    /// Returns an estimate of the heap memory (in bytes) used by the trie, where `value_size`
+    /// returns the heap memory owned by a value (e.g. `|_| 0` if it doesn't own any).
+    ///
+    /// The hashmaps are estimated from their capacity, so the actual allocation may be a bit
+    /// bigger (the buckets are rounded up to a power of two).
+    pub fn memory_usage_with(&self, value_size: impl Fn(&V) -> usize) -> usize {
+        self.trie.memory_usage_with(value_size)
+    }
+
+    /// Returns a read-only copy of the processor whose trie is stored as a double-array, which
+    /// is smaller and faster to traverse, see [`DoubleArrayKeywordProcessor`].
+    ///
+    /// The replacements (see `add_keyword_with_replacement()`) aren't copied.
+    pub fn to_double_array(&self) -> DoubleArrayKeywordProcessor<V, T>
+    where
+        V: Clone,
+        T: Clone,
+    {
+        DoubleArrayKeywordProcessor::new(self)
+    }
+
+    /// Returns a read-only copy of the processor whose trie is stored as a radix tree of the
+    /// bytes of the tokens, which is faster to traverse when the keywords share long prefixes,
+    /// see [`RadixKeywordProcessor`].
+    ///
+    /// The replacements (see `add_keyword_with_replacement()`) aren't copied.
+    pub fn to_radix_tree(&self) -> RadixKeywordProcessor<V, T>
+    where
+        V: Clone,
+        T: Clone,
+    {
+        RadixKeywordProcessor::new(self)
+    }
+
+    /// Returns a read-only copy of the processor that extracts the keywords in a time that is
+    /// linear in the number of tokens of the text, see [`CompiledKeywordProcessor`].
+    ///
+    /// The replacements (see `add_keyword_with_replacement()`) aren't copied.
+    pub fn freeze(&self) -> CompiledKeywordProcessor<V, T>
+    where
+        V: Clone,
+        T: Clone,
+    {
+        CompiledKeywordProcessor::new(self)
+    }
+
+    /// Converts the processor into a [`FrozenKeywordProcessor`], whose trie is stored in a few
+    /// flat arrays, so it extracts the keywords just like this one, but it's smaller and it
+    /// can't be modified anymore.
+    ///
+    /// Unlike `freeze()`, the processor isn't copied (so the values don't need to be `Clone`),
+    /// and the priorities are kept. The replacements (see `add_keyword_with_replacement()`)
+    /// are dropped.
+    pub fn into_frozen(self) -> FrozenKeywordProcessor<V, T> {
+        FrozenKeywordProcessor::new(self)
+    }
+
+    /// Prepares the processor for extraction once you are done adding keywords: the nodes that
+    /// don't lead to any keyword (e.g. from `reserve_prefix()`) are removed, and the spare
+    /// capacity of the trie is released (see `shrink_to_fit()`).
+    pub fn compact(&mut self) {
+        self.trie.prune();
+        self.replacements.prune();
+        self.shrink_to_fit();
+    }
+
+    /// Reserves room for at least `additional` more distinct first tokens (i.e. children of the
+    /// root of the trie), so bulk-loading keywords doesn't rehash the root over and over.
+    pub fn reserve(&mut self, additional: usize) {
+        self.trie.node_mut(ROOT).children.reserve(additional);
+    }
+
+    /// Reserves room for at least `additional` more tokens after `prefix`, e.g. when many
+    /// keywords are known to start with the same tokens.
+    ///
+    /// The nodes of `prefix` are inserted if they are missing, and they stay in the trie (even
+    /// if no keyword is added below them) until a keyword below them is removed, or the
+    /// processor is cleared.
+    pub fn reserve_prefix<S: AsRef<str> + ?Sized>(&mut self, prefix: &S, additional: usize) {
+        let tokens = self.tokenizer.tokenize(prefix.as_ref());
+        let matcher = Matcher::new(&self.tokenizer, self.case_mode);
+        let (node, _) = self.trie.get_or_insert(tokens, matcher);
+        node.children.reserve(additional);
+    }
+
+    /// Releases the spare capacity of the trie (i.e. of its arena and the children of all its
+    /// nodes), which is useful once you are done adding keywords.
+    ///
+    /// The nodes with few enough children also go back from a hashmap to a short vector, and
+    /// the tokens of the removed keywords are released.
+    pub fn shrink_to_fit(&mut self) {
+        self.trie.shrink_to_fit();
+    }
+
+    /// Returns `true` if `word` was added as a keyword (a prefix of a keyword doesn't count).
+    ///
+    /// The tokens are compared just like when extracting, so with `CaseMode::Insensitive`
+    /// `word` can be in any case.
+    pub fn contains<S: AsRef<str> + ?Sized>(&self, word: &S) -> bool {
+        self.get_value(word).is_some()
+    }
+
+    /// Returns the value of `word`.
+    pub fn get_value<S: AsRef<str> + ?Sized>(&self, word: &S) -> Option<&V> {
+        let mut node = self.trie.root();
+        for (_, token) in self.tokenizer.tokenize(word.as_ref()) {
+            node = node.child(token, self.matcher())?;
+        }
+        node.value()
+    }
+
+    /// Returns an iterator over all the keywords in the processor (in arbitrary order).
+    pub fn keywords(&self) -> impl ExactSizeIterator<Item = String> + '_ {
+        self.keywords_with_value().map(|(word, _)| word)
+    }
+
+    /// Returns an iterator over all the keywords and their value (in arbitrary order).
+    ///
+    /// The keywords are reconstructed by concatenating the tokens from the root of the trie to
+    /// each keyword's node.
+    pub fn keywords_with_value(&self) -> impl ExactSizeIterator<Item = (String, &V)> + '_ {
+        Keywords {
+            stack: vec![(String::new(), self.trie.root())],
+            remaining: self.len,
+        }
+    }
+
+    /// Returns an iterator over the keywords that start with the tokens of `prefix` (in
+    /// arbitrary order), including `prefix` itself if it's a keyword.
+    ///
+    /// The prefix is matched by whole tokens, so "New Yo" doesn't match "New York" (but
+    /// "New " does).
+    pub fn keywords_with_prefix<S: AsRef<str> + ?Sized>(
+        &self,
+        prefix: &S,
+    ) -> impl ExactSizeIterator<Item = String> + '_ {
+        let mut node = Some(self.trie.root());
+        // the keywords are reconstructed from the tokens in the trie, just like `keywords()`
+        let mut word = String::new();
+        for (_, token) in self.tokenizer.tokenize(prefix.as_ref()) {
+            let Some((key, child)) = node.and_then(|n| n.child_with_key(token, self.matcher()))
+            else {
+                node = None;
+                break;
+            };
+            word.push_str(key.as_str());
+            node = Some(child);
+        }
+        let remaining = node.map_or(0, NodeRef::keyword_count);
+        Keywords {
+            stack: node.map(|node| (word, node)).into_iter().collect(),
+            remaining,
+        }
+        .map(|(word, _)| word)
+    }
+
+    /// Returns the keywords that could complete `partial_text` (in arbitrary order), e.g.
+    /// "New Yo" -> "New York" and "New York City".
+    ///
+    /// Unlike `keywords_with_prefix()`, the last token of `partial_text` only needs to be the
+    /// start of a token of the keyword.
+    pub fn suggest<S: AsRef<str> + ?Sized>(
+        &self,
+        partial_text: &S,
+    ) -> impl ExactSizeIterator<Item = String> + '_ {
+        let mut tokens: Vec<_> = self.tokenizer.tokenize(partial_text.as_ref()).collect();
+        let partial_token = tokens.pop().map(|(_, token)| self.tokenizer.normalize(token));
+
+        let mut node = Some(self.trie.root());
+        let mut word = String::new();
+        for (_, token) in tokens {
+            let Some((key, child)) = node.and_then(|n| n.child_with_key(token, self.matcher()))
+            else {
+                node = None;
+                break;
+            };
+            word.push_str(key.as_str());
+            node = Some(child);
+        }
+        let stack: Vec<_> = match (node, partial_token) {
+            (None, _) => vec![],
+            (Some(node), None) => vec![(word, node)],
+            (Some(node), Some(partial_token)) => node
+                .children()
+                .filter(|(key, _)| starts_with(key.as_str(), &partial_token, self.case_mode))
+                .map(|(key, child)| (word.clone() + key.as_str(), child))
+                .collect(),
+        };
+        Keywords {
+            remaining: stack.iter().map(|(_, node)| node.keyword_count()).sum(),
+            stack,
+        }
+        .map(|(word, _)| word)
+    }
+
+    /// Removes `word` from the processor, returns `true` if it was there.
+    ///
+    /// Any node that is left without children (and isn't the end of another keyword) is also
+    /// removed, so the trie doesn't keep dead branches around.
+    pub fn remove_keyword<S: AsRef<str> + ?Sized>(&mut self, word: &S) -> bool {
+        let tokens: Vec<_> = self
+            .tokenizer
+            .tokenize(word.as_ref())
+            .map(|(_, token)| self.tokenizer.normalize(token))
+            .collect();
+        let removed = self.trie.remove(ROOT, &tokens, self.case_mode);
+        if removed {
+            self.len -= 1;
+            self.token_counts.remove(tokens.len());
+            self.replacements.remove(ROOT, &tokens, self.case_mode);
+        }
+        removed
+    }
+
+    /// Removes all the keywords of `iter` from the processor, returns how many were there.
+    ///
+    /// The keywords are first gathered into a trie of their own, so the nodes of a common
+    /// prefix are only visited once (and duplicates are only removed once).
+    pub fn remove_keywords_from_iter<I>(&mut self, iter: I) -> usize
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut words = Trie::default();
+        for word in iter {
+            let tokens = self.tokenizer.tokenize(word.as_ref());
+            words.get_or_insert(tokens, self.matcher()).0.set_value(());
+        }
+        let (words, case_mode) = (words.root(), self.case_mode);
+        let removed = self.trie.remove_all(ROOT, words, case_mode, 0, &mut self.token_counts);
+        self.len -= removed;
+        let mut token_counts = TokenCounts::default();
+        self.replacements.remove_all(ROOT, words, case_mode, 0, &mut token_counts);
+        removed
+    }
+
+    /// Returns the keywords in the text, each as the matched text, its value and its span.
+    pub fn extract_keywords_with_value<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a V, usize, usize)> + 'a {
+        self.extractor(text).map(|(value, start, end)| (&text[start..end], value, start, end))
+    }
+
+    /// Same as `extract_keywords_with_value()`, but the text is read incrementally from
+    /// `reader` (see `extract_keywords_from_reader()`), so each keyword is only its value and
+    /// its span in the stream.
+    pub fn extract_keywords_from_reader_with_value<'a, R: BufRead + 'a>(
+        &'a self,
+        reader: R,
+    ) -> impl Iterator<Item = io::Result<(&'a V, usize, usize)>> + 'a {
+        let to_item = |_: &Scanner<'a, V, T>, keyword| keyword;
+        ReaderKeywordExtractor::new(reader, self.scanner(), to_item)
+    }
+
+    /// Returns a [`Scanner`] that extracts the keywords of a text that is fed in chunks, e.g.
+    /// when the chunks come from a network stream, instead of a reader.
+    pub fn scanner(&self) -> Scanner<'_, V, T> {
+        Scanner::new(self.trie.root(), self.matcher(), self.max_keyword_tokens())
+    }
+
+    /// Returns `true` if any keyword is in the text.
+    ///
+    /// It stops at the first keyword it finds, and the rest of the text isn't even tokenized.
+    pub fn has_any_keyword(&self, text: &str) -> bool {
+        self.extractor(text).next().is_some()
+    }
+
+    /// Returns how many keywords are in the text, i.e. `extract_keywords_with_value(text)
+    /// .count()`, but without keeping all the tokens of the text in memory.
+    pub fn count_keywords(&self, text: &str) -> usize {
+        let mut count = 0;
+        self.scan(text, |_| {
+            count += 1;
+            ControlFlow::Continue(())
+        });
+        count
+    }
+
+    /// Calls `f` with each keyword in the text, until it returns `ControlFlow::Break` (then the
+    /// rest of the text isn't tokenized).
+    fn scan<'a>(
+        &'a self,
+        text: &str,
+        mut f: impl FnMut((&'a V, usize, usize)) -> ControlFlow<()>,
+    ) {
+        for keyword in self.extractor(text) {
+            if f(keyword).is_break() {
+                return;
+            }
+        }
+    }
+
+    /// Returns the keywords in the tokens of `text`, which are only tokenized as the extraction
+    /// goes (see `KeywordExtractor`).
+    fn extractor<'a, 't>(
+        &'a self,
+        text: &'t str,
+    ) -> KeywordExtractor<'a, 't, V, T, impl Iterator<Item = (usize, &'t str)> + use<'a, 't, V, T>>
+    {
+        let tokens = self.tokenizer.tokenize(text);
+        let lookahead = self.max_keyword_tokens();
+        KeywordExtractor::new(tokens, self.trie.root(), self.matcher(), lookahead, self.prioritized)
+    }
+
+    /// Replaces each keyword with `mask` (e.g. `"[REDACTED]"`, or `'*'` for as many asterisks
+    /// as the keyword has chars), e.g. to scrub personal data out of a text.
+    ///
+    /// Only the tokens that can still be part of a keyword are kept in memory, see
+    /// `count_keywords()`.
+    pub fn redact_keywords<'m>(&self, text: &str, mask: impl Into<Mask<'m>>) -> String {
+        let mask = mask.into();
+        let mut string = String::with_capacity(text.len());
+        let mut prev_end = 0;
+        self.scan(text, |(_, start, end)| {
+            string += &text[prev_end..start];
+            mask.push_to(&text[start..end], &mut string);
+            prev_end = end;
+            ControlFlow::Continue(())
+        });
+        string += &text[prev_end..];
+        string
+    }
+
+    /// Returns the parts of `text` that were matched as keywords (e.g. for highlighting them),
+    /// instead of their values.
+    pub fn extract_matches<'a>(&'a self, text: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.extract_keywords_with_value(text).map(|(matched_text, _, _, _)| matched_text)
+    }
+}
+
+impl<T: Tokenizer> KeywordProcessor<String, T> {
+    /// Adds `word` as a keyword, which will be extracted as itself.
+    ///
+    /// The processor owns its keywords, so `word` can be a `String` (that will be moved into
+    /// the processor), or any string that is converted into one.
+    #[inline]
+    pub fn add_keyword(&mut self, word: impl Into<String>) {
+        let word = word.into();
+        let matcher = Matcher::new(&self.tokenizer, self.case_mode);
+        let (node, token_count) = self.trie.get_or_insert(self.tokenizer.tokenize(&word), matcher);
+        if node.set_value(word) {
+            self.count_keyword(token_count);
+        }
+    }
+
+    #[inline]
+    pub fn add_keyword_with_clean_word(&mut self, word: &str, clean_word: impl Into<String>) {
+        self.add_keyword_with_value(word, clean_word.into());
+    }
+
+    /// Same as `add_keyword_with_clean_word()`, but the keyword is replaced with `replacement`
+    /// (e.g. a link) instead of its `clean_word`, by `replace_keywords()` and its variants.
+    ///
+    /// The replacement is kept until the keyword is removed, and it isn't serialized.
+    pub fn add_keyword_with_replacement(
+        &mut self,
+        word: &str,
+        clean_word: impl Into<String>,
+        replacement: impl Into<String>,
+    ) {
+        self.add_keyword_with_clean_word(word, clean_word);
+        let tokens = self.tokenizer.tokenize(word);
+        let matcher = Matcher::new(&self.tokenizer, self.case_mode);
+        self.replacements.get_or_insert(tokens, matcher).0.set_value(replacement.into());
+    }
+
+    /// Returns what a keyword is replaced with: its replacement if it has one, otherwise its
+    /// `clean_word`.
+    fn replacement<'a>(&'a self, m: &Match<'a>) -> &'a str {
+        self.replacement_of(m.clean_word, &m.keyword)
+    }
+
+    /// Same as `replacement()`, where `keyword` is the part of the text that was matched.
+    fn replacement_of<'a>(&'a self, clean_word: &'a str, keyword: &str) -> &'a str {
+        if !self.replacements.root().has_children() {
+            return clean_word;
+        }
+        let mut node = self.replacements.root();
+        for (_, token) in self.tokenizer.tokenize(keyword) {
+            match node.child(token, self.matcher()) {
+                Some(child) => node = child,
+                None => return clean_word,
+            }
+        }
+        node.value().map_or(clean_word, String::as_str)
+    }
+
+    pub fn add_keywords_from_iter<I>(&mut self, iter: I)
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        self.measured(|kp| {
+            for word in iter {
+                kp.add_keyword(word.as_ref());
+            }
+        });
+    }
+
+    pub fn add_keywords_with_clean_word_from_iter<I, W, C>(&mut self, iter: I)
+    where
+        I: IntoIterator<Item = (W, C)>,
+        W: AsRef<str>,
+        C: AsRef<str>,
+    {
+        self.measured(|kp| {
+            for (word, clean_word) in iter {
+                kp.add_keyword_with_clean_word(word.as_ref(), clean_word.as_ref());
+            }
+        });
+    }
+
+    /// Returns an estimate of the heap memory (in bytes) used by the trie and the clean words,
+    /// see `memory_usage_with()`.
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage_with(String::capacity)
+    }
+
+    /// Returns the `clean_word` that `word` will be replaced/extracted with.
+    pub fn get_clean_word<S: AsRef<str> + ?Sized>(&self, word: &S) -> Option<&str> {
+        self.get_value(word).map(String::as_str)
+    }
+
+    /// Returns an iterator over all the keywords and their `clean_word` (in arbitrary order).
+    pub fn keywords_with_clean_word(&self) -> impl ExactSizeIterator<Item = (String, &str)> + '_ {
+        self.keywords_with_value().map(|(word, clean_word)| (word, clean_word.as_str()))
+    }
+
+    // TODO: should reference to self be like this??
+    pub fn extract_keywords<'a>(&'a self, text: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.extract_keywords_with_span(text).map(|m| m.clean_word)
+    }
+
+    pub fn extract_keywords_with_span<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        self.extractor(text).map(|keyword| to_match(text, keyword))
+    }
+
+    /// Returns the first keyword in the text, i.e. `extract_keywords_with_span(text).next()`
+    /// (the rest of the text isn't even tokenized).
+    pub fn find_first<'a>(&'a self, text: &'a str) -> Option<Match<'a>> {
+        let mut first = None;
+        self.scan(text, |keyword| {
+            first = Some(to_match(text, keyword));
+            ControlFlow::Break(())
+        });
+        first
+    }
+
+    /// Calls `f` with each keyword in the text, until it returns `ControlFlow::Break`.
+    ///
+    /// It finds the same keywords as `extract_keywords_with_span()`, but only the tokens that
+    /// can still be part of a keyword are kept in memory (and the matches borrow the text).
+    pub fn for_each_keyword<'a>(
+        &'a self,
+        text: &'a str,
+        mut f: impl FnMut(Match<'a>) -> ControlFlow<()>,
+    ) {
+        self.scan(text, |keyword| f(to_match(text, keyword)));
+    }
+
+    /// Returns up to `max_matches` keywords (with their span), and whether there were more.
+    ///
+    /// The text is only scanned until the keyword after the last one returned, so that a text
+    /// with many keywords can't use a lot of time and memory.
+    pub fn extract_keywords_with_limit<'a>(
+        &'a self,
+        text: &'a str,
+        max_matches: usize,
+    ) -> (Vec<Match<'a>>, bool) {
+        let mut keywords = self.extract_keywords_with_span(text);
+        let matches: Vec<_> = keywords.by_ref().take(max_matches).collect();
+        let truncated = matches.len() == max_matches && keywords.next().is_some();
+        (matches, truncated)
+    }
+
+    /// Returns how many times each `clean_word` was found in the text.
+    pub fn extract_keyword_counts<'a>(&'a self, text: &'a str) -> HashMap<&'a str, usize> {
+        let mut counts = HashMap::new();
+        for keyword in self.extract_keywords(text) {
+            *counts.entry(keyword).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Returns the keywords in the text as `(matched_text, clean_word)` pairs, where the
+    /// matched text is the part of `text` that was matched (e.g. in its original case).
+    pub fn extract_keywords_with_clean_word<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a str)> + 'a {
+        self.extract_keywords_with_value(text)
+            .map(|(matched_text, clean_word, _, _)| (matched_text, clean_word.as_str()))
+    }
+
+    /// Same as `extract_keywords_with_span()`, but the span is in chars (i.e. Unicode scalar
+    /// values) instead of bytes (so it can't slice the text, unlike `Match::range()`).
+    pub fn extract_keywords_with_char_span<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        convert_spans(text, self.extract_keywords_with_span(text), |s| s.chars().count())
+    }
+
+    /// Same as `extract_keywords_with_span()`, but the span is in UTF-16 code units (e.g. the
+    /// indices of a JavaScript string) instead of bytes.
+    pub fn extract_keywords_with_utf16_span<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        convert_spans(text, self.extract_keywords_with_span(text), |s| s.encode_utf16().count())
+    }
+
+    /// Same as `extract_keywords_with_span()`, but the shortest keyword that starts at each
+    /// token is returned (e.g. "New York" instead of "New York City"), without looking ahead
+    /// for a longer one.
+    ///
+    /// It's faster when we only need to know whether (and where) the text has keywords. The
+    /// priorities are ignored.
+    pub fn extract_keywords_shortest<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        let tokens: Vec<_> = self.tokenizer.tokenize(text).collect();
+        let mut idx = 0;
+        std::iter::from_fn(move || {
+            while idx < tokens.len() {
+                match self.trie.root().step_shortest(&tokens, idx, self.matcher()) {
+                    Some((keyword, next_idx)) => {
+                        idx = next_idx;
+                        return Some(to_match(text, keyword));
+                    }
+                    None => idx += 1,
+                }
+            }
+            None
+        })
+    }
+
+    /// Same as `extract_keywords_with_span()`, but the span is in tokens (i.e. the index of
+    /// the first token of the keyword and the index after its last one), as returned by the
+    /// tokenizer for the whole text.
+    pub fn extract_keywords_with_token_span<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        let mut token_starts = self.tokenizer.tokenize(text).map(|(start, _)| start).peekable();
+        let mut token_idx = 0;
+        self.extract_keywords_with_span(text).map(move |m| {
+            // the keywords are sorted, so we only need to count the tokens until each offset
+            let mut count_tokens_before = |offset| {
+                while token_starts.next_if(|&token_start| token_start < offset).is_some() {
+                    token_idx += 1;
+                }
+                token_idx
+            };
+            let (start, end) = (count_tokens_before(m.start), count_tokens_before(m.end));
+            Match { start, end, ..m }
+        })
+    }
+
+    /// Same as `extract_keywords_with_span()`, but each keyword also comes with (up to)
+    /// `n_tokens` tokens of the text before and after it, e.g. to show a snippet of where it
+    /// was found.
+    pub fn extract_keywords_with_context<'a>(
+        &'a self,
+        text: &'a str,
+        n_tokens: usize,
+    ) -> impl Iterator<Item = MatchContext<'a>> + 'a {
+        let token_starts: Vec<_> = self.tokenizer.tokenize(text).map(|(start, _)| start).collect();
+        // the offset of the token at `idx`, or the end of the text
+        let offset = move |idx: usize| token_starts.get(idx).copied().unwrap_or(text.len());
+        self.extract_keywords_with_token_span(text).map(move |m| {
+            let (first, last) = (m.start, m.end);
+            let (start, end) = (offset(first), offset(last));
+            MatchContext {
+                left: &text[offset(first.saturating_sub(n_tokens))..start],
+                right: &text[end..offset(last.saturating_add(n_tokens))],
+                matched: Match { start, end, ..m },
+            }
+        })
+    }
+
+    /// Splits the text into sentences (as defined in the [Unicode Standard Annex #29]), and
+    /// returns each sentence that contains keywords, with their spans in `text`.
+    ///
+    /// The keywords are looked for in each sentence separately, so a keyword that spans two
+    /// sentences isn't found.
+    ///
+    /// [Unicode Standard Annex #29]: https://www.unicode.org/reports/tr29/
+    pub fn extract_sentences_with_keywords<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = (&'a str, Vec<Match<'a>>)> + 'a {
+        text.split_sentence_bound_indices().filter_map(|(offset, sentence)| {
+            let keywords: Vec<_> = self
+                .extract_keywords_with_span(sentence)
+                .map(|m| Match { start: offset + m.start, end: offset + m.end, ..m })
+                .collect();
+            (!keywords.is_empty()).then_some((sentence, keywords))
+        })
+    }
+
+    /// Same as `extract_keywords_with_span()`, but each token of the text can differ by up to
+    /// `max_edits` chars (i.e. the Levenshtein distance) from the token of the keyword, so
+    /// that "recieve" matches "receive".
+    ///
+    /// Only the tokens of the keyword that are longer than `max_edits` chars can differ, so
+    /// that short words (and the whitespace) still have to match exactly. It's much slower,
+    /// since each token is compared with all the tokens that could follow, and the priorities
+    /// are ignored.
+    pub fn extract_keywords_fuzzy<'a>(
+        &'a self,
+        text: &'a str,
+        max_edits: usize,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        let tokens: Vec<_> = self.tokenizer.tokenize(text).collect();
+        let mut idx = 0;
+        std::iter::from_fn(move || {
+            while idx < tokens.len() {
+                match self.trie.root().longest_fuzzy(&tokens, idx, max_edits, self.matcher()) {
+                    Some((clean_word, next_idx)) => {
+                        let (last_start, last_token) = tokens[next_idx - 1];
+                        let (start, end) = (tokens[idx].0, last_start + last_token.len());
+                        idx = next_idx;
+                        return Some(Match::new(&text[start..end], clean_word, start, end));
+                    }
+                    None => idx += 1,
+                }
+            }
+            None
+        })
+    }
+
+    /// Same as `extract_keywords_with_span()`, but the `skippable` tokens (e.g. "of", "the")
+    /// can appear inside a keyword in the text, even if they aren't in the keyword, so that
+    /// "bank america" matches "bank of america".
+    ///
+    /// The whitespace after a skipped token is also skipped, and a keyword can't start with a
+    /// skipped token. The priorities are ignored.
+    pub fn extract_keywords_skipping<'a, S: AsRef<str>>(
+        &'a self,
+        text: &'a str,
+        skippable: &'a [S],
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        let tokens: Vec<_> = self.tokenizer.tokenize(text).collect();
+        let is_skippable = move |token: &str| {
+            skippable.iter().any(|skippable| match self.case_mode {
+                CaseMode::Sensitive => skippable.as_ref() == token,
+                CaseMode::Insensitive => {
+                    UnicaseStr::new(skippable.as_ref()) == UnicaseStr::new(token)
+                }
+                CaseMode::AsciiInsensitive => skippable.as_ref().eq_ignore_ascii_case(token),
+            })
+        };
+        let (trie, mut idx) = (self.trie.root(), 0);
+        std::iter::from_fn(move || {
+            while idx < tokens.len() {
+                match trie.longest_skipping(&tokens, idx, self.matcher(), is_skippable) {
+                    Some((clean_word, next_idx)) => {
+                        let (last_start, last_token) = tokens[next_idx - 1];
+                        let (start, end) = (tokens[idx].0, last_start + last_token.len());
+                        idx = next_idx;
+                        return Some(Match::new(&text[start..end], clean_word, start, end));
+                    }
+                    None => idx += 1,
+                }
+            }
+            None
+        })
+    }
+
+    /// Same as `extract_keywords_with_span()`, but the text is first cleaned up from the
+    /// artifacts of line wrapping (e.g. in the text extracted from a PDF): the soft hyphens
+    /// and the hyphens at the end of a line are removed ("key-\nword" -> "keyword"), and the
+    /// other line breaks become a space ("New\nYork" -> "New York").
+    ///
+    /// The spans are still in the original `text`.
+    pub fn extract_keywords_dehyphenated<'a>(&'a self, text: &'a str) -> Vec<Match<'a>> {
+        let (normalized, spans) = dehyphenate(text);
+        self.extractor(&normalized)
+            .map(|(clean_word, start, end)| {
+                let (start, end) = (spans[start].0, spans[end - 1].1);
+                Match::new(&text[start..end], clean_word, start, end)
+            })
+            .collect()
+    }
+
+    /// Returns all the keywords in the text, even the ones that overlap with each other.
+    ///
+    /// Unlike `extract_keywords_with_span()` (which only yields the longest keyword and skips
+    /// its tokens), every keyword that starts at every token is returned, sorted by their start
+    /// and then by their end.
+    pub fn extract_keywords_all<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        let tokens = self.tokenizer.tokenize(text);
+        OverlappingKeywordExtractor::new(tokens, self.trie.root(), self.matcher())
+            .map(|keyword| to_match(text, keyword))
+    }
+
+    /// Same as `extract_keywords_with_span()`, but each keyword is preceded by the shorter
+    /// keywords that start at the same token (e.g. "machine" and then "machine learning").
+    ///
+    /// Unlike `extract_keywords_all()`, the keywords that start inside a match (e.g.
+    /// "learning") are still skipped.
+    pub fn extract_keywords_nested<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        let mut longest = self.extract_keywords_with_span(text).peekable();
+        // both are sorted by their start, so we only keep the keywords that start where one of
+        // the longest keywords does
+        self.extract_keywords_all(text).filter(move |m| {
+            while longest.next_if(|longest| longest.start < m.start).is_some() {}
+            longest.peek().is_some_and(|longest| longest.start == m.start)
+        })
+    }
+
+    /// Returns the longest keyword that starts at each token, even if it overlaps with the
+    /// previous one.
+    ///
+    /// E.g. with "big apple" and "apple pie" both are found in "big apple pie", but unlike
+    /// `extract_keywords_all()` the keywords nested at the start of a longer one (like "big"
+    /// in "big apple") aren't returned.
+    pub fn extract_overlapping_keywords<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        // the keywords that start at the same token are sorted by their end, so the longest
+        // one is the last of them
+        let mut keywords = self.extract_keywords_all(text).peekable();
+        std::iter::from_fn(move || {
+            let mut keyword = keywords.next()?;
+            while let Some(next) = keywords.next_if(|next| next.start == keyword.start) {
+                keyword = next;
+            }
+            Some(keyword)
+        })
+    }
+
+    /// Returns the keywords (with their span) of each document, together with the index of the
+    /// document in `docs`.
+    ///
+    /// The documents are processed lazily, one at a time, and the matches only borrow the
+    /// processor, not the documents (their `keyword` is a copy of the matched text).
+    pub fn extract_keywords_from_docs<'a, I>(
+        &'a self,
+        docs: I,
+    ) -> impl Iterator<Item = (usize, Vec<Match<'a>>)> + 'a
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        I::IntoIter: 'a,
+    {
+        docs.into_iter().enumerate().map(|(idx, doc)| {
+            let doc = doc.as_ref();
+            let keywords = self.extractor(doc).map(|(clean_word, start, end)| {
+                Match::new(doc[start..end].to_owned(), clean_word, start, end)
+            });
+            (idx, keywords.collect())
+        })
+    }
+
+    /// Same as `extract_keywords_with_span()`, but the text is read incrementally from
+    /// `reader`, and the spans are the byte offsets from the start of the stream.
+    ///
+    /// Only the text that wasn't scanned yet is kept in memory, which is at most the tokens of
+    /// the longest keyword (since a keyword can span multiple reads) plus the last read.
+    /// Once an error is returned (including for invalid UTF-8) the iterator ends.
+    pub fn extract_keywords_from_reader<'a, R: BufRead + 'a>(
+        &'a self,
+        reader: R,
+    ) -> impl Iterator<Item = io::Result<Match<'a>>> + 'a {
+        let to_item = |scanner: &Scanner<'a, String, T>, keyword| {
+            to_owned_match(&scanner.text, scanner.offset, keyword)
+        };
+        ReaderKeywordExtractor::new(reader, self.scanner(), to_item)
+    }
+
+    /// Replaces each keyword with its `clean_word` (or its replacement, see
+    /// `add_keyword_with_replacement()`).
+    pub fn replace_keywords(&self, text: &str) -> String {
+        self.replace_keywords_with(text, |m| Cow::Borrowed(self.replacement(m)))
+    }
+
+    /// Same as `replace_keywords()`, but the keywords are replaced in `text` itself.
+    ///
+    /// The text is edited in its own buffer if no replacement needs to be written over text
+    /// that wasn't moved yet, i.e. if the text up to each keyword never gets longer (then it's
+    /// edited from the start), or never gets shorter (then it's edited from the end).
+    /// Otherwise a new string is built, like `replace_keywords()`.
+    pub fn replace_keywords_in_place(&self, text: &mut String) {
+        let keywords: Vec<_> = self
+            .extractor(text)
+            .map(|(clean_word, start, end)| {
+                (self.replacement_of(clean_word, &text[start..end]), start, end)
+            })
+            .collect();
+        let growth = |&(replacement, start, end): &(&str, usize, usize)| {
+            replacement.len() as isize - (end - start) as isize
+        };
+        let mut total = 0;
+        let shrinks_forward = keywords.iter().all(|keyword| {
+            total += growth(keyword);
+            total <= 0
+        });
+        let mut total = 0;
+        let grows = keywords.iter().all(|keyword| {
+            total += growth(keyword);
+            total >= 0
+        });
+        if !shrinks_forward && !grows {
+            *text = self.replace_keywords(text);
+            return;
+        }
+
+        let mut bytes = std::mem::take(text).into_bytes();
+        let old_len = bytes.len();
+        if shrinks_forward {
+            // everything before `write` is done, and everything after `read` wasn't moved yet
+            let (mut read, mut write) = (0, 0);
+            for (replacement, start, end) in keywords {
+                bytes.copy_within(read..start, write);
+                write += start - read;
+                bytes[write..write + replacement.len()].copy_from_slice(replacement.as_bytes());
+                write += replacement.len();
+                read = end;
+            }
+            bytes.copy_within(read..old_len, write);
+            bytes.truncate(write + old_len - read);
+        } else {
+            // the same, but from the end of the text
+            bytes.resize((old_len as isize + total) as usize, 0);
+            let (mut read, mut write) = (old_len, bytes.len());
+            for (replacement, start, end) in keywords.into_iter().rev() {
+                write -= read - end;
+                bytes.copy_within(end..read, write);
+                write -= replacement.len();
+                bytes[write..write + replacement.len()].copy_from_slice(replacement.as_bytes());
+                read = start;
+            }
+        }
+        *text = String::from_utf8(bytes).expect("only whole strings were copied");
+    }
+
+    /// Same as `replace_keywords()`, but the text is written to `writer` (e.g. a file), instead
+    /// of building a `String`.
+    pub fn replace_keywords_to<W: io::Write>(&self, text: &str, mut writer: W) -> io::Result<()> {
+        self.replace_segments(text, |segment| writer.write_all(segment.as_bytes()))
+    }
+
+    /// Same as `replace_keywords_to()`, but for a `fmt::Write` (e.g. a `fmt::Formatter`).
+    pub fn replace_keywords_to_fmt<W: fmt::Write>(&self, text: &str, mut writer: W) -> fmt::Result {
+        self.replace_segments(text, |segment| writer.write_str(segment))
+    }
+
+    /// Calls `f` with each part of the text that is replaced, in order: the text between the
+    /// keywords and their replacement.
+    fn replace_segments<E>(
+        &self,
+        text: &str,
+        mut f: impl FnMut(&str) -> Result<(), E>,
+    ) -> Result<(), E> {
+        let mut prev_end = 0;
+        for m in self.extract_keywords_with_span(text) {
+            f(&text[prev_end..m.start])?;
+            f(self.replacement(&m))?;
+            prev_end = m.end;
+        }
+        f(&text[prev_end..])
+    }
+
+    /// Surrounds each keyword in the text with `before` and `after`, e.g. `"<mark>"` and
+    /// `"</mark>"`.
+    pub fn highlight_keywords(&self, text: &str, before: &str, after: &str) -> String {
+        self.replace_keywords_with(text, |m| Cow::Owned(format!("{before}{}{after}", m.keyword)))
+    }
+
+    /// Replaces each keyword with the `template`, filled with its `clean_word` and the matched
+    /// text.
+    pub fn replace_keywords_with_template(&self, text: &str, template: &Template) -> String {
+        self.replace_keywords_with(text, |m| {
+            Cow::Owned(template.render(m.clean_word, &m.keyword))
+        })
+    }
+
+    /// Replaces each keyword with the string returned by `f`, which is called with the match
+    /// of the keyword (so the replacement can depend on its `clean_word`, or on the matched
+    /// text, i.e. `m.keyword`).
+    pub fn replace_keywords_with<'a, F>(&'a self, text: &'a str, f: F) -> String
+    where
+        F: FnMut(&Match<'a>) -> Cow<'a, str>,
+    {
+        replace_matches(text, self.extract_keywords_with_span(text), f)
+    }
+
+    /// Same as `replace_keywords()`, but also returns where each keyword was in `text`, and
+    /// where its replacement is in the new text.
+    pub fn replace_keywords_with_edits<'a>(&'a self, text: &'a str) -> (String, Vec<Edit<'a>>) {
+        let mut string = String::with_capacity(text.len());
+        let mut edits = Vec::new();
+        let mut prev_end = 0;
+        for m in self.extract_keywords_with_span(text) {
+            string += &text[prev_end..m.start];
+            let start = string.len();
+            string += self.replacement(&m);
+            edits.push(Edit {
+                clean_word: m.clean_word,
+                original: m.range(),
+                replaced: start..string.len(),
+            });
+            prev_end = m.end;
+        }
+        string += &text[prev_end..];
+        (string, edits)
+    }
+
+    /// Same as `replace_keywords()`, but only the first `n` keywords are replaced, and the rest
+    /// of the text is copied as it is (without looking for more keywords).
+    pub fn replace_keywords_limited(&self, text: &str, n: usize) -> String {
+        let keywords = self.extract_keywords_with_span(text).take(n);
+        replace_matches(text, keywords, |m| Cow::Borrowed(self.replacement(m)))
+    }
+
+    /// Same as `replace_keywords()`, but the case of the matched text is applied to the
+    /// replacement, e.g. with the clean word "fruit": "apple" -> "fruit", "Apple" -> "Fruit"
+    /// and "APPLE" -> "FRUIT".
+    ///
+    /// A matched text in mixed case (e.g. "aPPle") leaves the replacement as it is.
+    pub fn replace_keywords_matching_case(&self, text: &str) -> String {
+        self.replace_keywords_with(text, |m| match_case(&m.keyword, self.replacement(m)))
+    }
+
+    /// Same as `replace_keywords()`, but the text is borrowed if there are no keywords in it,
+    /// instead of being copied.
+    pub fn replace_keywords_cow<'a>(&'a self, text: &'a str) -> Cow<'a, str> {
+        let mut keywords = self.extract_keywords_with_span(text).peekable();
+        if keywords.peek().is_none() {
+            return Cow::Borrowed(text);
+        }
+        Cow::Owned(replace_matches(text, keywords, |m| Cow::Borrowed(self.replacement(m))))
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<T: Tokenizer + Sync> KeywordProcessor<String, T> {
+    /// Same as `extract_keywords_with_span()`, but the text is split into chunks that are
+    /// scanned in parallel.
+    ///
+    /// The output is identical to the sequential one: each chunk is scanned on its own, from
+    /// its first token up to its end (a keyword can still end past it). Then the scans are
+    /// stitched in order, the sequential scan carries on past the end of a chunk (one token or
+    /// keyword at a time) until it reaches a token where the next chunk also started a
+    /// traversal, from that point on both scans are the same, so the keywords of the next chunk
+    /// are taken from there and the sequential scan jumps to its end.
+    ///
+    /// Unlike splitting the text with an overlap of `max_keyword_tokens()`, there is no match
+    /// to dedup at the boundaries, and no keyword that is found twice.
+    #[doc(alias = "par_extract_keywords")]
+    pub fn extract_keywords_par<'a>(&'a self, text: &'a str) -> Vec<Match<'a>> {
+        use rayon::prelude::*;
+
+        // below this the overhead of spawning the tasks isn't worth it
+        const MIN_CHUNK_TOKENS: usize = 1 << 12;
+
+        // a keyword can lose to one that starts after it, so the chunks can't be stitched
+        if self.prioritized {
+            return self.extract_keywords_with_span(text).collect();
+        }
+
+        let tokens: Vec<_> = self.tokenizer.tokenize(text).collect();
+        let chunk_len = (tokens.len() / (rayon::current_num_threads() * 4)).max(MIN_CHUNK_TOKENS);
+        let chunks: Vec<_> = (0..tokens.len())
+            .step_by(chunk_len)
+            .map(|start| start..(start + chunk_len).min(tokens.len()))
+            .collect();
+        let (trie, matcher) = (self.trie.root(), self.matcher());
+        let scans: Vec<_> = chunks
+            .par_iter()
+            .map(|chunk| ChunkScan::new(trie, matcher, &tokens, chunk.clone()))
+            .collect();
+
+        let mut keywords = Vec::new();
+        // the token that the sequential scan is at, or `None` once it reached the end
+        let mut idx = Some(0);
+        for (chunk, scan) in chunks.iter().zip(scans) {
+            while let Some(i) = idx.filter(|&i| i < chunk.end) {
+                if scan.traversal_starts.binary_search(&i).is_ok() {
+                    // from here on the scan of the chunk is the same as the sequential one
+                    let first = scan.keywords.partition_point(|&(start, _)| start < i);
+                    let scanned = scan.keywords[first..].iter().map(|&(_, kw)| to_match(text, kw));
+                    keywords.extend(scanned);
+                    idx = scan.end_idx;
+                    break;
+                }
+                // the previous chunk didn't meet this one yet, so we keep scanning sequentially
+                idx = match self.trie.root().step(&tokens, i, self.matcher()) {
+                    Step::Match(kw, next_idx) => {
+                        keywords.push(to_match(text, kw));
+                        Some(next_idx)
+                    }
+                    Step::NoMatch => Some(i + 1),
+                    Step::End => None,
+                };
+            }
+        }
+        keywords
+    }
+}
+
+/// The result of scanning a chunk of the tokens independently of the others.
+#[cfg(feature = "rayon")]
+struct ChunkScan<'a, V> {
+    /// Every token index the scan started a traversal from (in ascending order).
+    traversal_starts: Vec<usize>,
+    /// The keywords found, paired with the index of the token their traversal started from.
+    keywords: Vec<(usize, (&'a V, usize, usize))>,
+    /// The index that the scan stopped at (past the end of the chunk), or `None` if it
+    /// reached the end of the tokens.
+    end_idx: Option<usize>,
+}
+
+#[cfg(feature = "rayon")]
+impl<'a, V> ChunkScan<'a, V> {
+    fn new<T: Tokenizer>(
+        trie: NodeRef<'a, V>,
+        matcher: Matcher<'_, T>,
+        tokens: &[(usize, &str)],
+        chunk: std::ops::Range<usize>,
+    ) -> Self {
+        let mut scan = Self {
+            traversal_starts: Vec::new(),
+            keywords: Vec::new(),
+            end_idx: None,
+        };
+        let mut idx = chunk.start;
+        while idx < chunk.end {
+            scan.traversal_starts.push(idx);
+            match trie.step(tokens, idx, matcher) {
+                Step::Match(kw, next_idx) => {
+                    scan.keywords.push((idx, kw));
+                    idx = next_idx;
+                }
+                Step::NoMatch => idx += 1,
+                Step::End => return scan,
+            }
+        }
+        scan.end_idx = Some(idx);
+        scan
+    }
+}
+
+impl<'a, S, T> Extend<&'a S> for KeywordProcessor<String, T>
+where
+    S: AsRef<str> + ?Sized,
+    T: Tokenizer,
+{
+    fn extend<I: IntoIterator<Item = &'a S>>(&mut self, iter: I) {
+        self.add_keywords_from_iter(iter);
+    }
+}
+
+impl<'a, S, T> Extend<(&'a S, &'a S)> for KeywordProcessor<String, T>
+where
+    S: AsRef<str> + ?Sized,
+    T: Tokenizer,
+{
+    fn extend<I: IntoIterator<Item = (&'a S, &'a S)>>(&mut self, iter: I) {
+        self.add_keywords_with_clean_word_from_iter(iter);
+    }
+}
+
+impl<'a, S, T> FromIterator<&'a S> for KeywordProcessor<String, T>
+where
+    S: AsRef<str> + ?Sized,
+    T: Tokenizer + Default,
+{
+    fn from_iter<I: IntoIterator<Item = &'a S>>(iter: I) -> Self {
+        let mut kp = Self::default();
+        kp.extend(iter);
+        kp
+    }
+}
+
+impl<'a, S, T> FromIterator<(&'a S, &'a S)> for KeywordProcessor<String, T>
+where
+    S: AsRef<str> + ?Sized,
+    T: Tokenizer + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (&'a S, &'a S)>>(iter: I) -> Self {
+        let mut kp = Self::default();
+        kp.extend(iter);
+        kp
+    }
+}
+
+impl<T: Tokenizer> Extend<String> for KeywordProcessor<String, T> {
+    fn extend<I: IntoIterator<Item = String>>(&mut self, iter: I) {
+        for keyword in iter {
+            self.add_keyword(keyword);
+        }
+    }
+}
+
+/// The clean words are moved into the trie, without copying them.
+impl<T: Tokenizer> Extend<(String, String)> for KeywordProcessor<String, T> {
+    fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, iter: I) {
+        for (keyword, clean_word) in iter {
+            self.add_keyword_with_clean_word(&keyword, clean_word);
+        }
+    }
+}
+
+impl<T: Tokenizer + Default> FromIterator<String> for KeywordProcessor<String, T> {
+    fn from_iter<I: IntoIterator<Item = String>>(iter: I) -> Self {
+        let mut kp = Self::default();
+        kp.extend(iter);
+        kp
+    }
+}
+
+impl<T: Tokenizer + Default> FromIterator<(String, String)> for KeywordProcessor<String, T> {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        let mut kp = Self::default();
+        kp.extend(iter);
+        kp
+    }
+}
+
+#[cfg(feature = "memory-stats")]
+impl<V, T> KeywordProcessor<V, T> {
+    /// Returns the heap memory used by the process while the keywords were added with
+    /// `add_keywords_from_iter()` (or `add_keywords_with_clean_word_from_iter()` and
+    /// `add_sorted_keywords_with_value()`), or `None` if they never were, or if the
+    /// [`TrackingAllocator`](crate::TrackingAllocator) isn't the global allocator.
+    ///
+    /// ```
+    /// use flashtext2::{KeywordProcessor, TrackingAllocator};
+    ///
+    /// #[global_allocator]
+    /// static ALLOCATOR: TrackingAllocator = TrackingAllocator::new(std::alloc::System);
+    ///
+    /// let mut kp = KeywordProcessor::new();
+    /// kp.add_keywords_from_iter((0..1_000).map(|i| format!("keyword {i}")));
+    /// let stats = kp.memory_stats().unwrap();
+    /// assert!(stats.peak_increase() >= kp.memory_usage());
+    /// ```
+    pub fn memory_stats(&self) -> Option<crate::MemoryStats> {
+        self.memory_stats
+    }
+
+    /// Runs `f`, and records the most heap memory that was used meanwhile.
+    fn measured<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let Some(start) = crate::memory::start() else {
+            return f(self);
+        };
+        let output = f(self);
+        let peak = crate::memory::peak();
+        let stats = self.memory_stats.get_or_insert(crate::MemoryStats { start, peak });
+        stats.peak = stats.peak.max(peak);
+        output
+    }
+}
+
+#[cfg(not(feature = "memory-stats"))]
+impl<V, T> KeywordProcessor<V, T> {
+    #[inline]
+    fn measured<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        f(self)
+    }
+}
+
+/// The processor is serialized as a sequence of `(keyword, value)` pairs, instead of the
+/// trie itself, so the format doesn't depend on how the trie is implemented.
+///
+/// The rest is lost: the replacements (see `add_keyword_with_replacement()`) and the
+/// priorities aren't part of the format.
+#[cfg(feature = "serde")]
+impl<V: serde::Serialize, T: Tokenizer> serde::Serialize for KeywordProcessor<V, T> {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.keywords_with_value())
+    }
+}
+
+/// The trie is rebuilt from the `(keyword, value)` pairs.
+///
+/// The `CaseMode` isn't part of the format, so the processor is always case sensitive, use
+/// `case_insensitive::KeywordProcessor` to deserialize a case insensitive one.
+#[cfg(feature = "serde")]
+impl<'de, V, T> serde::Deserialize<'de> for KeywordProcessor<V, T>
+where
+    V: serde::Deserialize<'de>,
+    T: Tokenizer + Default,
+{
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::default().deserialize_into(deserializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<V, T: Tokenizer> KeywordProcessor<V, T> {
+    /// Adds the `(keyword, value)` pairs of `deserializer` to this processor.
+    pub(crate) fn deserialize_into<'de, D>(self, deserializer: D) -> Result<Self, D::Error>
+    where
+        V: serde::Deserialize<'de>,
+        D: serde::Deserializer<'de>,
+    {
+        struct Visitor<V, T>(KeywordProcessor<V, T>);
+
+        impl<'de, V, T> serde::de::Visitor<'de> for Visitor<V, T>
+        where
+            V: serde::Deserialize<'de>,
+            T: Tokenizer,
+        {
+            type Value = KeywordProcessor<V, T>;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a sequence of (keyword, value) pairs")
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                let mut kp = self.0;
+                while let Some((word, value)) = seq.next_element::<(String, V)>()? {
+                    kp.add_keyword_with_value(&word, value);
+                }
+                Ok(kp)
+            }
+        }
+
+        deserializer.deserialize_seq(Visitor(self))
+    }
+}
+
+struct Keywords<'a, V> {
+    // a depth-first traversal, where each node is paired with the keyword that leads to it
+    stack: Vec<(String, NodeRef<'a, V>)>,
+    remaining: usize,
+}
+
+impl<'a, V> Iterator for Keywords<'a, V> {
+    type Item = (String, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((word, node)) = self.stack.pop() {
+            for (token, child) in node.children() {
+                let mut child_word = word.clone();
+                child_word.push_str(token.as_str());
+                self.stack.push((child_word, child));
+            }
+            if let Some(value) = node.value() {
+                self.remaining -= 1;
+                return Some((word, value));
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<V> ExactSizeIterator for Keywords<'_, V> {}
+
+// the tokens can have a shorter lifetime than the trie, so that a text that is only borrowed
+// while extracting (e.g. after normalizing it) still yields values that borrow the trie
+struct KeywordExtractor<'a, 't, V, T, I> {
+    // the tokens are pulled from `tokens` as the extraction goes, and only the ones that the
+    // traversals can still reach are kept in `buffer` (where `idx` is the current one)
+    tokens: I,
+    buffer: Vec<(usize, &'t str)>,
+    idx: usize,
+    lookahead: usize,
+    trie: NodeRef<'a, V>,
+    matcher: Matcher<'a, T>,
+    prioritized: bool,
+}
+
+impl<'a, 't, V, T, I> KeywordExtractor<'a, 't, V, T, I>
+where
+    T: Tokenizer,
+    I: Iterator<Item = (usize, &'t str)>,
+{
+    fn new(
+        tokens: I,
+        trie: NodeRef<'a, V>,
+        matcher: Matcher<'a, T>,
+        max_keyword_tokens: usize,
+        prioritized: bool,
+    ) -> Self {
+        // a traversal can't go deeper than the longest keyword, so that's how many tokens we
+        // need from the current one (plus the one that ends the traversal), but with the
+        // priorities we also look for the keywords that start inside the current one
+        let lookahead = match prioritized {
+            true => 2 * max_keyword_tokens + 1,
+            false => max_keyword_tokens + 1,
+        };
+        Self {
+            tokens,
+            buffer: Vec::with_capacity(2 * lookahead),
+            idx: 0,
+            lookahead,
+            trie,
+            matcher,
+            prioritized,
+        }
+    }
+
+    /// Drops the tokens before the current one (once there are enough of them), and pulls the
+    /// ones that a traversal from it can reach.
+    #[inline]
+    fn fill(&mut self) {
+        if self.idx >= self.lookahead {
+            self.buffer.drain(..self.idx);
+            self.idx = 0;
+        }
+        let missing = (self.idx + self.lookahead).saturating_sub(self.buffer.len());
+        self.buffer.extend(self.tokens.by_ref().take(missing));
+    }
+
+    /// Same as `next()`, but the keywords with a higher priority win (see
+    /// `add_keyword_with_priority()`).
+    fn next_by_priority(&mut self) -> Option<(&'a V, usize, usize)> {
+        loop {
+            self.fill();
+            let len = self.buffer.len();
+            if self.idx >= len {
+                return None;
+            }
+            // the keywords that start inside the current one are only looked for until we
+            // find one with a higher priority, and then we only consider the keywords that
+            // end before it
+            let mut limit = len;
+            while let Some((keyword, priority, next_idx)) =
+                self.trie.step_by_priority(&self.buffer, self.idx, limit, self.matcher)
+            {
+                let beaten_at = (self.idx + 1..next_idx).find(|&idx| {
+                    self.trie
+                        .step_by_priority(&self.buffer, idx, len, self.matcher)
+                        .is_some_and(|(_, other_priority, _)| other_priority > priority)
+                });
+                match beaten_at {
+                    Some(idx) => limit = idx,
+                    None => {
+                        self.idx = next_idx;
+                        return Some(keyword);
+                    }
+                }
+            }
+            self.idx += 1;
+        }
+    }
+}
+
+impl<'a, 't, V, T, I> Iterator for KeywordExtractor<'a, 't, V, T, I>
+where
+    T: Tokenizer,
+    I: Iterator<Item = (usize, &'t str)>,
+{
+    // TODO: return a struct or smth instead of a tuple
+    type Item = (&'a V, usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.prioritized {
+            return self.next_by_priority();
+        }
+        loop {
+            self.fill();
+            match self.trie.step(&self.buffer, self.idx, self.matcher) {
+                Step::Match(keyword, next_idx) => {
+                    self.idx = next_idx;
+                    return Some(keyword);
+                }
+                Step::NoMatch => self.idx += 1,
+                Step::End => {
+                    self.idx = self.buffer.len();
+                    return None;
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = self.buffer.len() - self.idx;
+        (0, self.tokens.size_hint().1.map(|len| len + buffered))
+    }
+}
+
+struct OverlappingKeywordExtractor<'a, V, T> {
+    tokens: Vec<(usize, &'a str)>,
+    trie: NodeRef<'a, V>,
+    matcher: Matcher<'a, T>,
+    // the token that the current traversal started from
+    traversal_start_idx: usize,
+    // the next token of the current traversal, and the node we reached so far
+    idx: usize,
+    node: NodeRef<'a, V>,
+}
+
+impl<'a, V, T> OverlappingKeywordExtractor<'a, V, T> {
+    fn new(
+        tokens: impl Iterator<Item = (usize, &'a str)>,
+        trie: NodeRef<'a, V>,
+        matcher: Matcher<'a, T>,
+    ) -> Self {
+        Self {
+            tokens: tokens.collect(),
+            trie,
+            matcher,
+            traversal_start_idx: 0,
+            idx: 0,
+            node: trie,
+        }
+    }
+}
+
+impl<'a, V, T: Tokenizer> Iterator for OverlappingKeywordExtractor<'a, V, T> {
+    type Item = (&'a V, usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.traversal_start_idx < self.tokens.len() {
+            let child = self
+                .tokens
+                .get(self.idx)
+                .and_then(|&(_, token)| self.node.child(token, self.matcher));
+
+            if let Some(child) = child {
+                let (token_start_idx, token) = self.tokens[self.idx];
+                self.idx += 1;
+                self.node = child;
+                if let Some(value) = child.value() {
+                    let start = self.tokens[self.traversal_start_idx].0;
+                    return Some((value, start, token_start_idx + token.len()));
+                }
+            } else {
+                // we can't go any deeper, so we start again from the next token
+                self.traversal_start_idx += 1;
+                self.idx = self.traversal_start_idx;
+                self.node = self.trie;
+            }
+        }
+        None
+    }
+}
+
+/// Extracts the keywords of a text that is fed in chunks (e.g. from a network stream), see
+/// `KeywordProcessor::scanner()`.
+///
+/// The keywords are the same as the ones of `extract_keywords_with_span()` on the whole text,
+/// and their spans are the byte offsets from the start of the first chunk. A keyword is only
+/// returned once it's certain that the next chunks won't make it longer (or part of another
+/// one), so the last ones are returned by `finish()`.
+///
+/// ```
+/// use flashtext2::KeywordProcessor;
+///
+/// let mut kp = KeywordProcessor::new();
+/// kp.add_keywords_from_iter(["New York", "New York City"]);
+///
+/// let mut scanner = kp.scanner();
+/// let mut found: Vec<_> = scanner.push("I love New Yo").collect();
+/// found.extend(scanner.push("rk City, and New "));
+/// found.extend(scanner.push("York"));
+/// found.extend(scanner.finish());
+/// assert_eq!(found, [("New York City", 7, 20), ("New York", 26, 34)]);
+/// ```
+pub struct Scanner<'a, V = String, T = WordBoundTokenizer> {
+    trie: NodeRef<'a, V>,
+    matcher: Matcher<'a, T>,
+    max_keyword_tokens: usize,
+    // the text that wasn't dropped yet, which starts at the byte `offset` of the stream
+    text: String,
+    offset: usize,
+    // the spans (in the stream) of the tokens that can't change anymore but weren't scanned
+    // yet, and the start of the last tokens, which are tokenized again with the next chunk
+    tokens: Vec<(usize, usize)>,
+    tokenized: usize,
+    // the end of the text that was scanned, which is only kept until the keywords found in it
+    // are returned
+    scanned: usize,
+    keywords: VecDeque<(&'a V, usize, usize)>,
+}
+
+impl<'a, V, T: Tokenizer> Scanner<'a, V, T> {
+    fn new(trie: NodeRef<'a, V>, matcher: Matcher<'a, T>, max_keyword_tokens: usize) -> Self {
+        Self {
+            trie,
+            matcher,
+            max_keyword_tokens,
+            text: String::new(),
+            offset: 0,
+            tokens: Vec::new(),
+            tokenized: 0,
+            scanned: 0,
+            keywords: VecDeque::new(),
+        }
+    }
+
+    /// Same as `push()`, but each keyword is its value and its span.
+    pub fn push_with_value(
+        &mut self,
+        chunk: &str,
+    ) -> impl Iterator<Item = (&'a V, usize, usize)> + '_ {
+        self.text.push_str(chunk);
+        self.scan(false);
+        self.keywords.drain(..)
+    }
+
+    /// Same as `finish()`, but each keyword is its value and its span.
+    pub fn finish_with_value(mut self) -> impl Iterator<Item = (&'a V, usize, usize)> {
+        self.scan(true);
+        self.keywords.into_iter()
+    }
+
+    /// Finds the keywords in `self.text` (that are certain not to change by reading more
+    /// text, unless it's the end of the stream), and drops the text that was scanned.
+    ///
+    /// Only the text after the tokens that can't change anymore is tokenized, so each chunk
+    /// is tokenized once (besides the last tokens before it).
+    fn scan(&mut self, eof: bool) {
+        self.text.drain(..self.scanned - self.offset);
+        self.offset = self.scanned;
+
+        let tail_start = self.tokenized;
+        let tail = &self.text[tail_start - self.offset..];
+        let new_tokens: Vec<_> = self
+            .matcher
+            .tokenizer
+            .tokenize(tail)
+            .map(|(start, token)| (tail_start + start, token.len()))
+            .collect();
+        // the boundaries of the last two tokens may still change (e.g. "can" + "'t")
+        let final_len = match eof {
+            true => new_tokens.len(),
+            false => new_tokens.len().saturating_sub(2),
+        };
+        let text_end = self.offset + self.text.len();
+        self.tokenized = new_tokens.get(final_len).map_or(text_end, |&(start, _)| start);
+        self.tokens.extend(&new_tokens[..final_len]);
+
+        let tokens: Vec<_> = self
+            .tokens
+            .iter()
+            .map(|&(start, len)| (start, &self.text[start - self.offset..][..len]))
+            .collect();
+        let mut idx = 0;
+        // unless it's the end of the stream, a traversal that can reach the last token could
+        // continue once we read more text (a traversal looks at one token past the longest
+        // keyword at most)
+        while eof || idx + self.max_keyword_tokens < tokens.len() {
+            match self.trie.step(&tokens, idx, self.matcher) {
+                Step::Match(keyword, next_idx) => {
+                    self.keywords.push_back(keyword);
+                    idx = next_idx;
+                }
+                Step::NoMatch => idx += 1,
+                Step::End => break,
+            }
+        }
+
+        self.scanned = match eof {
+            true => text_end,
+            false => self.tokens.get(idx).map_or(self.tokenized, |&(start, _)| start),
+        };
+        self.tokens.drain(..idx.min(self.tokens.len()));
+    }
+}
+
+impl<'a, T: Tokenizer> Scanner<'a, String, T> {
+    /// Appends `chunk` to the text, returns the keywords that it completed.
+    pub fn push(&mut self, chunk: &str) -> impl Iterator<Item = Match<'a>> + '_ {
+        self.text.push_str(chunk);
+        self.scan(false);
+        let (text, offset) = (&self.text, self.offset);
+        self.keywords.drain(..).map(move |keyword| to_owned_match(text, offset, keyword))
+    }
+
+    /// Ends the text, returns the keywords that were still pending.
+    pub fn finish(mut self) -> impl Iterator<Item = Match<'a>> {
+        self.scan(true);
+        let Self { text, offset, keywords, .. } = self;
+        keywords.into_iter().map(move |keyword| to_owned_match(&text, offset, keyword))
+    }
+}
+
+struct ReaderKeywordExtractor<'a, V, R, T, F> {
+    reader: R,
+    scanner: Scanner<'a, V, T>,
+    // converts a keyword into an item, with the scanner that still has its text
+    to_item: F,
+    // the bytes that were read but aren't a complete UTF-8 char yet
+    bytes: Vec<u8>,
+    eof: bool,
+    done: bool,
+}
+
+impl<'a, V, R: BufRead, T: Tokenizer, F> ReaderKeywordExtractor<'a, V, R, T, F> {
+    fn new(reader: R, scanner: Scanner<'a, V, T>, to_item: F) -> Self {
+        Self {
+            reader,
+            scanner,
+            to_item,
+            bytes: Vec::new(),
+            eof: false,
+            done: false,
+        }
+    }
+
+    /// Appends the next chunk of the stream to the text of the scanner.
+    fn read(&mut self) -> io::Result<()> {
+        let chunk = loop {
+            match self.reader.fill_buf() {
+                Ok(chunk) => break chunk,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            }
+        };
+        if chunk.is_empty() {
+            self.eof = true;
+            if !self.bytes.is_empty() {
+                return Err(invalid_utf8());
+            }
+            return Ok(());
+        }
+        let len = chunk.len();
+        self.bytes.extend_from_slice(chunk);
+        self.reader.consume(len);
+
+        // a char can be split between two chunks, in which case we keep its first bytes
+        let valid_len = match std::str::from_utf8(&self.bytes) {
+            Ok(_) => self.bytes.len(),
+            Err(err) if err.error_len().is_none() => err.valid_up_to(),
+            Err(_) => return Err(invalid_utf8()),
+        };
+        self.scanner.text.push_str(std::str::from_utf8(&self.bytes[..valid_len]).unwrap());
+        self.bytes.drain(..valid_len);
+        Ok(())
+    }
+}
+
+impl<'a, V, R, T, F, I> Iterator for ReaderKeywordExtractor<'a, V, R, T, F>
+where
+    R: BufRead,
+    T: Tokenizer,
+    F: FnMut(&Scanner<'a, V, T>, (&'a V, usize, usize)) -> I,
+{
+    type Item = io::Result<I>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(keyword) = self.scanner.keywords.pop_front() {
+                return Some(Ok((self.to_item)(&self.scanner, keyword)));
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(err) = self.read() {
+                self.done = true;
+                return Some(Err(err));
+            }
+            self.scanner.scan(self.eof);
+            self.done = self.eof;
+        }
+    }
+}
+
+/// Replaces each of the `matches` in `text` with the string returned by `f`.
+fn replace_matches<'a>(
+    text: &'a str,
+    matches: impl Iterator<Item = Match<'a>>,
+    mut f: impl FnMut(&Match<'a>) -> Cow<'a, str>,
+) -> String {
+    let mut string = String::with_capacity(text.len());
+    // the `prev_end` is necessary to adjust the span as we replace the `word` with its
+    // `clean_word`. because if their length is not the same, the next `(start, end)` span
+    // won't be accurate.
+    let mut prev_end = 0;
+    for m in matches {
+        string += &text[prev_end..m.start];
+        string += &f(&m);
+        prev_end = m.end;
+    }
+    string += &text[prev_end..];
+
+    // if a `word` is bigger than its `clean_word` then it will over-allocate
+    string.shrink_to_fit();
+
+    string
+}
+
+/// Returns `replacement` in the case of `matched`: lowercase, uppercase, or capitalized (i.e.
+/// only its first cased char is uppercase), otherwise `replacement` is returned as it is.
+fn match_case<'a>(matched: &str, replacement: &'a str) -> Cow<'a, str> {
+    let mut cased = matched.chars().filter(|c| c.is_lowercase() || c.is_uppercase());
+    let Some(first) = cased.next() else {
+        return Cow::Borrowed(replacement);
+    };
+    let (mut lower, mut upper) = (first.is_lowercase(), first.is_uppercase());
+    let mut capitalized = upper;
+    for c in cased {
+        lower &= c.is_lowercase();
+        upper &= c.is_uppercase();
+        capitalized &= c.is_lowercase();
+    }
+    // a single uppercase char (e.g. "C") is more likely to be capitalized than uppercase
+    if capitalized {
+        let mut chars = replacement.chars();
+        return match chars.next() {
+            Some(first) => Cow::Owned(first.to_uppercase().chain(chars).collect()),
+            None => Cow::Borrowed(replacement),
+        };
+    }
+    if lower {
+        return Cow::Owned(replacement.to_lowercase());
+    }
+    if upper {
+        return Cow::Owned(replacement.to_uppercase());
+    }
+    Cow::Borrowed(replacement)
+}
+
+/// Same as `to_match()`, but the part of `text` (which starts at the byte `offset` of a stream)
+/// that was matched is copied, since it's dropped once more of the stream is scanned.
+fn to_owned_match<'a>(
+    text: &str,
+    offset: usize,
+    (clean_word, start, end): (&'a String, usize, usize),
+) -> Match<'a> {
+    Match::new(text[start - offset..end - offset].to_owned(), clean_word, start, end)
+}
+
+/// Borrows the `clean_word` of a match as a `&str`, and the part of `text` that was matched.
+fn to_match<'a>(text: &'a str, (clean_word, start, end): (&'a String, usize, usize)) -> Match<'a> {
+    Match::new(&text[start..end], clean_word, start, end)
+}
+
+/// Converts the byte spans of the keywords in `text` to the units counted by `len`.
+fn convert_spans<'a>(
+    text: &'a str,
+    keywords: impl Iterator<Item = Match<'a>> + 'a,
+    len: fn(&str) -> usize,
+) -> impl Iterator<Item = Match<'a>> + 'a {
+    // the keywords don't overlap and are sorted by their span, so we only need to count the
+    // units since the end of the previous keyword
+    let mut prev_end = (0, 0); // (byte index, converted index)
+    keywords.map(move |m| {
+        let converted_start = prev_end.1 + len(&text[prev_end.0..m.start]);
+        let converted_end = converted_start + len(&m.keyword);
+        prev_end = (m.end, converted_end);
+        Match { start: converted_start, end: converted_end, ..m }
+    })
+}
+
+fn invalid_utf8() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8")
+}
+
+/// The outcome of traversing the trie from a given token.
+enum Step<'a, V> {
+    /// The longest keyword that starts at the token, and the index to continue from.
+    Match((&'a V, usize, usize), usize),
+    /// There is no keyword that starts at the token, so we continue from the next one.
+    NoMatch,
+    /// There is no keyword that starts at the token, nor after it.
+    End,
+}
+
+impl<'a, V> NodeRef<'a, V> {
+    #[inline]
+    fn step<T: Tokenizer>(
+        self,
+        tokens: &[(usize, &str)],
+        traversal_start_idx: usize,
+        matcher: Matcher<'_, T>,
+    ) -> Step<'a, V> {
+        let mut node = self;
+        // a keyword is essentially a collection/sequence of tokens, and we continue from the
+        // token right after it
+        let mut longest_sequence = None;
+        // we need to remember the index that we started traversing the trie, to be able to
+        // roll back our `idx` if we are following a "false" sequence, and also to know the
+        // span of the sequence if we do find a match.
+        let mut idx = traversal_start_idx;
+
+        while idx < tokens.len() {
+            let (token_start_idx, token) = tokens[idx];
+            idx += 1;
+
+            if let Some(child) = node.child(token, matcher) {
+                node = child;
+                if let Some(value) = node.value() {
+                    let span = (tokens[traversal_start_idx].0, token_start_idx + token.len());
+                    longest_sequence = Some(((value, span.0, span.1), idx));
+                }
+            } else {
+                return match longest_sequence {
+                    Some((kw, next_idx)) => Step::Match(kw, next_idx),
+                    None => Step::NoMatch,
+                };
+            }
+        }
+
+        // we reached the end of the tokens, but there may still be a keyword that starts at
+        // one of the tokens after `traversal_start_idx`
+        match longest_sequence {
+            Some((kw, next_idx)) => Step::Match(kw, next_idx),
+            None if traversal_start_idx + 1 < tokens.len() => Step::NoMatch,
+            None => Step::End,
+        }
+    }
+
+    /// Same as `step()`, but the keyword with the highest priority is picked (and then the
+    /// longest), and the index to continue from is the one right after it.
+    ///
+    /// Only the tokens before `limit` are traversed, and `None` means that there is no keyword
+    /// that starts at the token.
+    fn step_by_priority<T: Tokenizer>(
+        self,
+        tokens: &[(usize, &str)],
+        traversal_start_idx: usize,
+        limit: usize,
+        matcher: Matcher<'_, T>,
+    ) -> Option<((&'a V, usize, usize), u32, usize)> {
+        let mut node = self;
+        let mut best: Option<((&'a V, usize, usize), u32, usize)> = None;
+        for idx in traversal_start_idx..limit {
+            let (token_start_idx, token) = tokens[idx];
+            let Some(child) = node.child(token, matcher) else {
+                break;
+            };
+            node = child;
+            if let Some(value) = node.value() {
+                // a longer keyword only replaces the current one if its priority isn't lower
+                if best.is_none_or(|(_, priority, _)| node.priority() >= priority) {
+                    let span = (tokens[traversal_start_idx].0, token_start_idx + token.len());
+                    best = Some(((value, span.0, span.1), node.priority(), idx + 1));
+                }
+            }
+        }
+        best
+    }
+
+    /// Same as `step()`, but the traversal stops at the first (i.e. shortest) keyword, and the
+    /// index to continue from is the one right after it.
+    fn step_shortest<T: Tokenizer>(
+        self,
+        tokens: &[(usize, &str)],
+        traversal_start_idx: usize,
+        matcher: Matcher<'_, T>,
+    ) -> Option<((&'a V, usize, usize), usize)> {
+        let mut node = self;
+        for (idx, &(token_start_idx, token)) in tokens.iter().enumerate().skip(traversal_start_idx)
+        {
+            node = node.child(token, matcher)?;
+            if let Some(value) = node.value() {
+                let span = (tokens[traversal_start_idx].0, token_start_idx + token.len());
+                return Some(((value, span.0, span.1), idx + 1));
+            }
+        }
+        None
+    }
+
+    /// Returns the longest keyword that starts at `idx` (and the index after it), where the
+    /// `skippable` tokens are ignored (see `extract_keywords_skipping()`).
+    fn longest_skipping<T: Tokenizer>(
+        self,
+        tokens: &[(usize, &str)],
+        traversal_start_idx: usize,
+        matcher: Matcher<'_, T>,
+        is_skippable: impl Fn(&str) -> bool,
+    ) -> Option<(&'a V, usize)> {
+        let mut node = self;
+        let mut longest = None;
+        let mut idx = traversal_start_idx;
+        while let Some(&(_, token)) = tokens.get(idx) {
+            idx += 1;
+            if let Some(child) = node.child(token, matcher) {
+                node = child;
+                if let Some(value) = node.value() {
+                    longest = Some((value, idx));
+                }
+            } else if idx - 1 > traversal_start_idx && is_skippable(token) {
+                while tokens.get(idx).is_some_and(|&(_, token)| is_whitespace(token)) {
+                    idx += 1;
+                }
+            } else {
+                break;
+            }
+        }
+        longest
+    }
+
+    /// Returns the longest keyword that starts at `idx` (and the index after it), where each
+    /// token can differ by up to `max_edits` chars (see `extract_keywords_fuzzy()`).
+    fn longest_fuzzy<T: Tokenizer>(
+        self,
+        tokens: &[(usize, &str)],
+        idx: usize,
+        max_edits: usize,
+        matcher: Matcher<'_, T>,
+    ) -> Option<(&'a V, usize)> {
+        let &(_, token) = tokens.get(idx)?;
+        let token = matcher.tokenizer.normalize(token);
+        let mut longest: Option<(&'a V, usize)> = None;
+        for (key, child) in self.children() {
+            let key = key.as_str();
+            let allowed_edits = if key.chars().count() > max_edits { max_edits } else { 0 };
+            if edit_distance(key, &token, allowed_edits, matcher.case_mode).is_none() {
+                continue;
+            }
+            let found = child
+                .longest_fuzzy(tokens, idx + 1, max_edits, matcher)
+                .or(child.value().map(|value| (value, idx + 1)));
+            if let Some((value, end)) = found {
+                if longest.is_none_or(|(_, longest_end)| end > longest_end) {
+                    longest = Some((value, end));
+                }
+            }
+        }
+        longest
+    }
+}
+
+/// Removes the soft hyphens and the hyphens at the end of a line from `text`, and replaces the
+/// other line breaks with a space.
+///
+/// Also returns the span in `text` of each byte of the new string.
+fn dehyphenate(text: &str) -> (String, Vec<(usize, usize)>) {
+    let mut normalized = String::with_capacity(text.len());
+    let mut spans = Vec::with_capacity(text.len());
+    let mut chars = text.char_indices().peekable();
+    let is_line_break = |&(_, c): &(usize, char)| c == '\r' || c == '\n';
+    while let Some((idx, c)) = chars.next() {
+        let mut end = idx + c.len_utf8();
+        let c = match c {
+            '\u{AD}' => continue,
+            '-' if chars.peek().is_some_and(is_line_break) => {
+                while chars.next_if(is_line_break).is_some() {}
+                continue;
+            }
+            '\r' | '\n' => {
+                while let Some((idx, c)) = chars.next_if(is_line_break) {
+                    end = idx + c.len_utf8();
+                }
+                ' '
+            }
+            c => c,
+        };
+        normalized.push(c);
+        spans.resize(normalized.len(), (idx, end));
+    }
+    (normalized, spans)
+}
+
+/// Whether the token is only whitespace (e.g. " " or "\n").
+fn is_whitespace(token: &str) -> bool {
+    token.chars().all(char::is_whitespace)
+}
+
+fn starts_with(token: &str, prefix: &str, case_mode: CaseMode) -> bool {
+    match case_mode {
+        CaseMode::Sensitive => token.starts_with(prefix),
+        // the same folding as the keys (e.g. "STRASS" is the start of "straße")
+        CaseMode::Insensitive => fold_case(token).starts_with(&fold_case(prefix)),
+        CaseMode::AsciiInsensitive => {
+            token.len() >= prefix.len()
+                && token.is_char_boundary(prefix.len())
+                && token[..prefix.len()].eq_ignore_ascii_case(prefix)
+        }
+    }
+}
+
+/// Applies the full Unicode case folding, which the keys of a case insensitive processor are
+/// compared with (see `UnicaseStr`).
+fn fold_case(s: &str) -> String {
+    UniCase::unicode(s).to_folded_case()
+}
+
+/// Returns the Levenshtein distance (in chars) between `a` and `b`, or `None` if it's more
+/// than `max`.
+fn edit_distance(a: &str, b: &str, max: usize, case_mode: CaseMode) -> Option<usize> {
+    let chars = |s: &str| -> Vec<char> {
+        match case_mode {
+            CaseMode::Sensitive => s.chars().collect(),
+            CaseMode::Insensitive => fold_case(s).chars().collect(),
+            CaseMode::AsciiInsensitive => s.chars().map(|c| c.to_ascii_lowercase()).collect(),
+        }
+    };
+    let (a, b) = (chars(a), chars(b));
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    // the distances between the prefixes of `a` and the previous prefix of `b`
+    let mut row: Vec<usize> = (0..=a.len()).collect();
+    for (j, &b_char) in b.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = j + 1;
+        for (i, &a_char) in a.iter().enumerate() {
+            let substitution = diagonal + usize::from(a_char != b_char);
+            diagonal = row[i + 1];
+            row[i + 1] = substitution.min(row[i] + 1).min(diagonal + 1);
+        }
+    }
+    let distance = row[a.len()];
+    (distance <= max).then_some(distance)
+}
@@ -0,0 +1,459 @@
+use std::borrow::Cow;
+use unicase::UniCase;
+use crate::mapped::{case_mode_code, MAGIC, VERSION};
+use crate::{CaseMode, KeywordProcessor, Match, Tokenizer, WordBoundTokenizer};
+
+const ROOT: u32 = 0;
+/// The value index of a node that isn't the end of a keyword (and the index of a missing
+/// child, or of a node without a full table of children).
+const NONE: u32 = u32::MAX;
+/// Ends each token of a path, it's never part of a UTF-8 string.
+const SEPARATOR: u8 = 0xFF;
+/// Past this many children, a node gets a table with a slot for each byte, instead of
+/// searching its sorted children.
+const MAX_SORTED: usize = 48;
+
+/// A read-only copy of a [`KeywordProcessor`], whose trie is stored as a radix tree of the
+/// bytes of the tokens, see `KeywordProcessor::to_radix_tree()`.
+///
+/// The path of a keyword is the bytes of its (normalized and case folded) tokens, each followed
+/// by a separator, and the nodes with a single child are merged with it, so a token of the text
+/// is matched byte by byte (instead of being hashed at each level of the trie). That's faster
+/// when the keywords share long prefixes, i.e. when the traversals go deep into the trie.
+///
+/// The keywords are matched like `KeywordProcessor::extract_keywords()` does, except that
+/// their priorities are ignored (the longest keyword always wins).
+///
+/// ```
+/// use flashtext2::KeywordProcessor;
+///
+/// let mut kp = KeywordProcessor::new();
+/// kp.add_keywords_from_iter(["New York", "New York City", "York"]);
+/// let radix = kp.to_radix_tree();
+///
+/// let text = "New York City and York";
+/// assert_eq!(radix.extract_keywords(text).collect::<Vec<_>>(), ["New York City", "York"]);
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct RadixKeywordProcessor<V = String, T = WordBoundTokenizer> {
+    nodes: Vec<RadixNode>,
+    // the bytes on the edge that leads to each node (after the byte of the edge itself)
+    labels: Vec<u8>,
+    // the children of each node, as the byte of their edge (sorted) and their index
+    child_bytes: Vec<u8>,
+    child_ids: Vec<u32>,
+    // the children of the nodes that have too many of them to be searched, by byte
+    tables: Vec<[u32; 256]>,
+    values: Vec<V>,
+    tokenizer: T,
+    case_mode: CaseMode,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub(crate) struct RadixNode {
+    pub label_start: u32,
+    pub label_len: u32,
+    pub value_idx: u32,
+    pub children_start: u32,
+    pub children_len: u32,
+    pub table: u32,
+}
+
+/// A position in the tree: a node, and how many bytes of its label were matched.
+type State = (u32, u32);
+
+impl<V: Clone, T: Tokenizer + Clone> RadixKeywordProcessor<V, T> {
+    /// # Panics
+    ///
+    /// If the tree has more than `u32::MAX - 1` nodes (or bytes in its labels).
+    pub(crate) fn new(kp: &KeywordProcessor<V, T>) -> Self {
+        let mut radix = Self {
+            nodes: Vec::new(),
+            labels: Vec::new(),
+            child_bytes: Vec::new(),
+            child_ids: Vec::new(),
+            tables: Vec::new(),
+            values: Vec::with_capacity(kp.len()),
+            tokenizer: kp.tokenizer().clone(),
+            case_mode: kp.case_mode(),
+        };
+        // the path of each keyword, and the index of its value
+        let mut paths = Vec::with_capacity(kp.len());
+        let mut stack = vec![(kp.trie(), Vec::new())];
+        while let Some((node, path)) = stack.pop() {
+            if let Some(value) = node.value() {
+                paths.push((path.clone(), radix.values.len() as u32));
+                radix.values.push(value.clone());
+            }
+            for (key, child) in node.children() {
+                let mut child_path = path.clone();
+                let key = fold(Cow::Borrowed(key.as_str()), radix.case_mode);
+                child_path.extend_from_slice(key.as_bytes());
+                child_path.push(SEPARATOR);
+                stack.push((child, child_path));
+            }
+        }
+        paths.sort_unstable();
+        // two keys that are equal for the case mode can't both be in the trie, but be safe
+        paths.dedup_by(|(a, _), (b, _)| a == b);
+        radix.build(&paths, 0);
+        radix.shrink_to_fit();
+        radix
+    }
+
+    /// Adds the node of the (sorted) `paths` that all share their first `depth` bytes, and
+    /// its descendants, returns its index.
+    fn build(&mut self, paths: &[(Vec<u8>, u32)], depth: usize) -> u32 {
+        let id = u32::try_from(self.nodes.len()).ok().filter(|&id| id != NONE);
+        let id = id.expect("too many nodes");
+        let (first, last) = (&paths[0].0, &paths[paths.len() - 1].0);
+        let label_len = first[depth..]
+            .iter()
+            .zip(&last[depth..])
+            .take_while(|(a, b)| a == b)
+            .count();
+        let label_start = u32::try_from(self.labels.len()).expect("too many nodes");
+        self.labels.extend_from_slice(&first[depth..depth + label_len]);
+        self.nodes.push(RadixNode {
+            label_start,
+            label_len: label_len as u32,
+            value_idx: NONE,
+            children_start: 0,
+            children_len: 0,
+            table: NONE,
+        });
+
+        let depth = depth + label_len;
+        // only the first path can end here, since it's the shortest one
+        let mut rest = paths;
+        if first.len() == depth {
+            self.nodes[id as usize].value_idx = paths[0].1;
+            rest = &paths[1..];
+        }
+        let mut children = Vec::new();
+        while let Some((path, _)) = rest.first() {
+            let byte = path[depth];
+            let group_len = rest.partition_point(|(path, _)| path[depth] == byte);
+            children.push((byte, self.build(&rest[..group_len], depth + 1)));
+            rest = &rest[group_len..];
+        }
+
+        let node = &mut self.nodes[id as usize];
+        node.children_start = self.child_ids.len() as u32;
+        node.children_len = children.len() as u32;
+        if children.len() > MAX_SORTED {
+            node.table = self.tables.len() as u32;
+            let mut table = [NONE; 256];
+            for &(byte, child) in &children {
+                table[byte as usize] = child;
+            }
+            self.tables.push(table);
+        } else {
+            self.child_bytes.extend(children.iter().map(|&(byte, _)| byte));
+            self.child_ids.extend(children.iter().map(|&(_, child)| child));
+        }
+        id
+    }
+
+    fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+        self.labels.shrink_to_fit();
+        self.child_bytes.shrink_to_fit();
+        self.child_ids.shrink_to_fit();
+        self.tables.shrink_to_fit();
+        self.values.shrink_to_fit();
+    }
+}
+
+impl<V, T: Tokenizer> RadixKeywordProcessor<V, T> {
+    pub fn tokenizer(&self) -> &T {
+        &self.tokenizer
+    }
+
+    pub fn case_mode(&self) -> CaseMode {
+        self.case_mode
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Returns the number of nodes in the tree (including the root), which is at most twice
+    /// the number of keywords.
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns an estimate of the heap memory (in bytes) used by the processor, where
+    /// `value_size` returns the heap memory owned by a value, see
+    /// `KeywordProcessor::memory_usage_with()`.
+    pub fn memory_usage_with(&self, value_size: impl Fn(&V) -> usize) -> usize {
+        let values: usize = self.values.iter().map(value_size).sum();
+        self.nodes.capacity() * std::mem::size_of::<RadixNode>()
+            + self.labels.capacity()
+            + self.child_bytes.capacity()
+            + self.child_ids.capacity() * std::mem::size_of::<u32>()
+            + self.tables.capacity() * std::mem::size_of::<[u32; 256]>()
+            + self.values.capacity() * std::mem::size_of::<V>()
+            + values
+    }
+
+    pub fn contains<S: AsRef<str> + ?Sized>(&self, word: &S) -> bool {
+        self.get_value(word).is_some()
+    }
+
+    pub fn get_value<S: AsRef<str> + ?Sized>(&self, word: &S) -> Option<&V> {
+        let value_idx = self.lookup(&self.tokenizer, self.case_mode, word.as_ref())?;
+        Some(&self.values[value_idx as usize])
+    }
+
+    /// Returns the keywords in the text, each as the matched text, its value and its span.
+    pub fn extract_keywords_with_value<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a V, usize, usize)> + 'a {
+        RadixExtractor::new(self, &self.tokenizer, self.case_mode, text).map(
+            |(value_idx, start, end)| {
+                (&text[start..end], &self.values[value_idx as usize], start, end)
+            },
+        )
+    }
+}
+
+impl<T: Tokenizer> RadixKeywordProcessor<String, T> {
+    /// Returns an estimate of the heap memory (in bytes) used by the processor, see
+    /// `memory_usage_with()`.
+    pub fn memory_usage(&self) -> usize {
+        self.memory_usage_with(String::capacity)
+    }
+
+    pub fn get_clean_word<S: AsRef<str> + ?Sized>(&self, word: &S) -> Option<&str> {
+        self.get_value(word).map(String::as_str)
+    }
+
+    /// Returns the processor serialized in a format that is read without being deserialized,
+    /// see [`MappedKeywordProcessor`](crate::MappedKeywordProcessor).
+    ///
+    /// The tokenizer isn't serialized.
+    ///
+    /// # Panics
+    ///
+    /// If the clean words take more than `u32::MAX` bytes.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let values_len: usize = self.values.iter().map(String::len).sum();
+        let values_len = u32::try_from(values_len).expect("the clean words are too long");
+        let mut words = vec![u32::from_le_bytes(MAGIC), VERSION, case_mode_code(self.case_mode)];
+        words.extend(
+            [
+                self.nodes.len(),
+                self.child_ids.len(),
+                self.tables.len(),
+                self.values.len(),
+                self.labels.len(),
+            ]
+            .map(|len| len as u32),
+        );
+        words.push(values_len);
+        for node in &self.nodes {
+            words.extend([
+                node.label_start,
+                node.label_len,
+                node.value_idx,
+                node.children_start,
+                node.children_len,
+                node.table,
+            ]);
+        }
+        words.extend(&self.child_ids);
+        words.extend(self.tables.iter().flatten());
+        let mut offset = 0;
+        words.push(offset);
+        for value in &self.values {
+            offset += value.len() as u32;
+            words.push(offset);
+        }
+
+        let mut bytes: Vec<_> = words.into_iter().flat_map(u32::to_le_bytes).collect();
+        bytes.extend(&self.labels);
+        bytes.extend(&self.child_bytes);
+        for value in &self.values {
+            bytes.extend(value.as_bytes());
+        }
+        bytes
+    }
+
+    pub fn extract_keywords<'a>(&'a self, text: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.extract_keywords_with_span(text).map(|m| m.clean_word)
+    }
+
+    pub fn extract_keywords_with_span<'a>(
+        &'a self,
+        text: &'a str,
+    ) -> impl Iterator<Item = Match<'a>> + 'a {
+        self.extract_keywords_with_value(text)
+            .map(|(_, clean_word, start, end)| {
+                Match::new(&text[start..end], clean_word, start, end)
+            })
+    }
+}
+
+impl<V, T> RadixArrays for RadixKeywordProcessor<V, T> {
+    #[inline]
+    fn node(&self, id: u32) -> RadixNode {
+        self.nodes[id as usize]
+    }
+
+    #[inline]
+    fn label(&self, idx: u32) -> u8 {
+        self.labels[idx as usize]
+    }
+
+    #[inline]
+    fn child_bytes(&self, start: u32, len: u32) -> &[u8] {
+        &self.child_bytes[start as usize..(start + len) as usize]
+    }
+
+    #[inline]
+    fn child_id(&self, idx: u32) -> u32 {
+        self.child_ids[idx as usize]
+    }
+
+    #[inline]
+    fn table_child(&self, table: u32, byte: u8) -> u32 {
+        self.tables[table as usize][byte as usize]
+    }
+}
+
+/// The arrays of a radix tree, which are either owned by a [`RadixKeywordProcessor`], or read
+/// from a buffer by a `MappedKeywordProcessor`, so both are traversed the same way.
+pub(crate) trait RadixArrays {
+    fn node(&self, id: u32) -> RadixNode;
+
+    fn label(&self, idx: u32) -> u8;
+
+    /// Returns the bytes of the edges of `len` children, starting at the child `start`.
+    fn child_bytes(&self, start: u32, len: u32) -> &[u8];
+
+    fn child_id(&self, idx: u32) -> u32;
+
+    fn table_child(&self, table: u32, byte: u8) -> u32;
+
+    #[inline]
+    fn step(&self, (node, matched): State, byte: u8) -> Option<State> {
+        let n = self.node(node);
+        if matched < n.label_len {
+            let label_byte = self.label(n.label_start + matched);
+            return (label_byte == byte).then_some((node, matched + 1));
+        }
+        let child = match n.table {
+            NONE => {
+                let bytes = self.child_bytes(n.children_start, n.children_len);
+                let idx = bytes.binary_search(&byte).ok()?;
+                self.child_id(n.children_start + idx as u32)
+            }
+            table => self.table_child(table, byte),
+        };
+        (child != NONE).then_some((child, 0))
+    }
+
+    /// Returns the state after the (folded) token.
+    #[inline]
+    fn step_token(&self, mut state: State, token: &str) -> Option<State> {
+        for &byte in token.as_bytes() {
+            state = self.step(state, byte)?;
+        }
+        self.step(state, SEPARATOR)
+    }
+
+    /// Returns the index of the value of the keyword that ends at `state`.
+    #[inline]
+    fn value_idx(&self, (node, matched): State) -> Option<u32> {
+        let n = self.node(node);
+        (matched == n.label_len && n.value_idx != NONE).then_some(n.value_idx)
+    }
+
+    /// Returns the index of the value of the keyword `word`.
+    fn lookup<T: Tokenizer>(&self, tokenizer: &T, case_mode: CaseMode, word: &str) -> Option<u32> {
+        let mut state = (ROOT, 0);
+        for (_, token) in tokenizer.tokenize(word) {
+            state = self.step_token(state, &fold(tokenizer.normalize(token), case_mode))?;
+        }
+        self.value_idx(state)
+    }
+}
+
+/// Returns the form of the (normalized) token that its path is made of.
+#[inline]
+pub(crate) fn fold(token: Cow<'_, str>, case_mode: CaseMode) -> Cow<'_, str> {
+    match case_mode {
+        // the same folding as the one that `UniCase` compares with
+        CaseMode::Insensitive if !token.is_ascii() => {
+            Cow::Owned(UniCase::unicode(&*token).to_folded_case())
+        }
+        CaseMode::AsciiInsensitive | CaseMode::Insensitive
+            if token.bytes().any(|b| b.is_ascii_uppercase()) =>
+        {
+            let mut token = token.into_owned();
+            token.make_ascii_lowercase();
+            Cow::Owned(token)
+        }
+        CaseMode::AsciiInsensitive | CaseMode::Insensitive => token,
+        CaseMode::Sensitive => token,
+    }
+}
+
+/// Finds the leftmost-longest keywords, returns the index of their value and their span.
+pub(crate) struct RadixExtractor<'a, A> {
+    arrays: &'a A,
+    // the span and the folded form of each token
+    tokens: Vec<(usize, usize, Cow<'a, str>)>,
+    idx: usize,
+}
+
+impl<'a, A: RadixArrays> RadixExtractor<'a, A> {
+    pub fn new<T>(arrays: &'a A, tokenizer: &T, case_mode: CaseMode, text: &'a str) -> Self
+    where
+        T: Tokenizer,
+    {
+        let tokens = tokenizer
+            .tokenize(text)
+            .map(|(start, token)| {
+                (start, start + token.len(), fold(tokenizer.normalize(token), case_mode))
+            })
+            .collect();
+        Self { arrays, tokens, idx: 0 }
+    }
+}
+
+impl<A: RadixArrays> Iterator for RadixExtractor<'_, A> {
+    type Item = (u32, usize, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.idx < self.tokens.len() {
+            let start_idx = self.idx;
+            let mut state = (ROOT, 0);
+            let mut longest = None;
+            for (idx, (_, end, token)) in self.tokens.iter().enumerate().skip(start_idx) {
+                let Some(next_state) = self.arrays.step_token(state, token) else {
+                    break;
+                };
+                state = next_state;
+                if let Some(value_idx) = self.arrays.value_idx(state) {
+                    longest = Some((value_idx, *end, idx + 1));
+                }
+            }
+            match longest {
+                Some((value_idx, end, next_idx)) => {
+                    self.idx = next_idx;
+                    return Some((value_idx, self.tokens[start_idx].0, end));
+                }
+                None => self.idx += 1,
+            }
+        }
+        None
+    }
+}
@@ -0,0 +1,105 @@
+use std::fmt;
+
+/// A replacement for the keywords, where `{clean}` is replaced with the `clean_word` of the
+/// keyword, and `{keyword}` with the text that was matched (use `{{` and `}}` for literal
+/// braces).
+///
+/// It's parsed once, and then applied to each match by
+/// `KeywordProcessor::replace_keywords_with_template()`.
+///
+/// ```
+/// use flashtext2::{KeywordProcessor, Template};
+///
+/// let mut kp = KeywordProcessor::new();
+/// kp.add_keyword_with_clean_word("rust", "https://www.rust-lang.org");
+///
+/// let template = Template::new("[{keyword}]({clean})").unwrap();
+/// assert_eq!(
+///     kp.replace_keywords_with_template("I love Rust and rust", &template),
+///     "I love Rust and [rust](https://www.rust-lang.org)"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    CleanWord,
+    Keyword,
+}
+
+impl Template {
+    pub fn new(template: &str) -> Result<Self, TemplateError> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.char_indices().peekable();
+        while let Some((idx, c)) = chars.next() {
+            match c {
+                '{' if chars.next_if(|&(_, c)| c == '{').is_some() => literal.push('{'),
+                '}' if chars.next_if(|&(_, c)| c == '}').is_some() => literal.push('}'),
+                '{' => {
+                    let rest = &template[idx + 1..];
+                    let Some(len) = rest.find('}') else {
+                        return Err(TemplateError::Unclosed(idx));
+                    };
+                    let part = match &rest[..len] {
+                        "clean" => Part::CleanWord,
+                        "keyword" => Part::Keyword,
+                        name => return Err(TemplateError::UnknownPlaceholder(name.to_string())),
+                    };
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(part);
+                    // skip the name and the closing brace
+                    while chars.next_if(|&(i, _)| i <= idx + 1 + len).is_some() {}
+                }
+                '}' => return Err(TemplateError::Unopened(idx)),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Returns the replacement of a keyword, given its `clean_word` and the matched text.
+    pub fn render(&self, clean_word: &str, keyword: &str) -> String {
+        let mut string = String::new();
+        for part in &self.parts {
+            string += match part {
+                Part::Literal(literal) => literal,
+                Part::CleanWord => clean_word,
+                Part::Keyword => keyword,
+            };
+        }
+        string
+    }
+}
+
+/// The reason a [`Template`] couldn't be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TemplateError {
+    /// A placeholder other than `{clean}` and `{keyword}`.
+    UnknownPlaceholder(String),
+    /// A `{` (at the given byte offset) without a matching `}`.
+    Unclosed(usize),
+    /// A `}` (at the given byte offset) without a matching `{`.
+    Unopened(usize),
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownPlaceholder(name) => write!(f, "unknown placeholder `{{{name}}}`"),
+            Self::Unclosed(idx) => write!(f, "unclosed `{{` at {idx}"),
+            Self::Unopened(idx) => write!(f, "unopened `}}` at {idx}"),
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
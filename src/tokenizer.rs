@@ -0,0 +1,625 @@
+use std::borrow::Cow;
+use std::sync::Arc;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::{is_nfc_quick, is_nfkc_quick, IsNormalized, UnicodeNormalization};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Splits the keywords and the text into the tokens that make up the trie.
+///
+/// The same tokenizer is used to add the keywords and to extract them, so the tokens of a
+/// keyword only need to be consistent with the tokens of the text it should match.
+///
+/// Keywords are reconstructed (by `keywords()`, and when serializing) by concatenating their
+/// normalized tokens, so a tokenizer that skips part of the text (e.g. whitespace) or changes
+/// the tokens will return keywords that differ from the ones that were added.
+/// And when reading from a stream, appending text to the end of the input must only change
+/// the last two tokens.
+pub trait Tokenizer {
+    /// Returns the tokens of `text`, each with its byte offset in `text`.
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't;
+
+    /// Returns the form of `token` that is stored in the trie, and that the tokens of the text
+    /// are looked up with (e.g. its NFC normalization), so that different tokens can match.
+    ///
+    /// The spans are still the ones of the tokens in the text. By default the token is left
+    /// as it is.
+    #[inline]
+    fn normalize<'t>(&self, token: &'t str) -> Cow<'t, str> {
+        Cow::Borrowed(token)
+    }
+}
+
+/// A tokenizer can be borrowed, e.g. to share one that holds a big dictionary between many
+/// processors.
+impl<T: Tokenizer> Tokenizer for &T {
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        (**self).tokenize(text)
+    }
+
+    #[inline]
+    fn normalize<'t>(&self, token: &'t str) -> Cow<'t, str> {
+        (**self).normalize(token)
+    }
+}
+
+/// The default tokenizer, which splits the text on the word boundaries defined in the
+/// [Unicode Standard Annex #29](https://www.unicode.org/reports/tr29/).
+///
+/// For example `"Hello world!"` becomes: `["Hello", " ", "world", "!"]`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WordBoundTokenizer;
+
+impl Tokenizer for WordBoundTokenizer {
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        text.split_word_bound_indices()
+    }
+}
+
+/// Splits the text into its extended grapheme clusters (i.e. what is displayed as one char),
+/// as defined in the [Unicode Standard Annex #29](https://www.unicode.org/reports/tr29/).
+///
+/// Like `CharTokenizer`, the keywords match anywhere, but a char is never separated from its
+/// combining marks, and the emoji sequences (e.g. "👍🏽", "🇫🇷" or "👨‍👩‍👧") are single tokens, so
+/// they can be keywords on their own.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct GraphemeTokenizer;
+
+impl Tokenizer for GraphemeTokenizer {
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        text.grapheme_indices(true)
+    }
+}
+
+/// A faster alternative to `WordBoundTokenizer`, which doesn't follow the Unicode rules: a
+/// token is either a run of alphanumeric chars, a run of whitespace, or any other single char.
+///
+/// For example `"Hello,  world!"` becomes: `["Hello", ",", "  ", "world", "!"]`, and unlike
+/// `WordBoundTokenizer` "can't" becomes `["can", "'", "t"]` and "3.14" `["3", ".", "14"]`.
+/// It's meant for texts like logs, where the word bounds are simple and the tokenization
+/// dominates the extraction time.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SimpleTokenizer;
+
+impl Tokenizer for SimpleTokenizer {
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        split_runs(text, |c| {
+            if c.is_alphanumeric() {
+                CharClass::Word
+            } else if c.is_whitespace() {
+                CharClass::Space
+            } else {
+                CharClass::Other
+            }
+        })
+    }
+}
+
+/// Same as `SimpleTokenizer`, but some chars other than the alphanumeric ones are also part of
+/// the words (like the non-word boundaries of the Python flashtext), e.g. with `'_'` and `'-'`
+/// "foo_bar" and "COVID-19" are single tokens.
+///
+/// ```
+/// use flashtext2::{KeywordProcessor, NonWordBoundaries};
+///
+/// let mut kp = KeywordProcessor::builder()
+///     .tokenizer(NonWordBoundaries::new(['_', '-']))
+///     .build();
+/// kp.add_keyword("COVID-19");
+/// kp.add_keyword("COVID");
+/// assert_eq!(kp.extract_keywords("COVID-19, COVID-ish").count(), 1);
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NonWordBoundaries {
+    // shared with the iterators of the tokens, which can't borrow the tokenizer
+    chars: Arc<[char]>,
+}
+
+impl NonWordBoundaries {
+    pub fn new(chars: impl IntoIterator<Item = char>) -> Self {
+        Self { chars: chars.into_iter().collect() }
+    }
+
+    /// Makes `c` part of the words.
+    ///
+    /// The tokenizer must not change once keywords were added with it, since they wouldn't
+    /// be split like the text anymore.
+    pub fn add_non_word_boundary(&mut self, c: char) {
+        if !self.chars.contains(&c) {
+            self.chars = self.chars.iter().copied().chain([c]).collect();
+        }
+    }
+
+    /// Replaces the chars that are part of the words (besides the alphanumeric ones).
+    pub fn set_non_word_boundaries(&mut self, chars: impl IntoIterator<Item = char>) {
+        self.chars = chars.into_iter().collect();
+    }
+
+    /// Returns the chars that are part of the words (besides the alphanumeric ones).
+    pub fn non_word_boundaries(&self) -> &[char] {
+        &self.chars
+    }
+}
+
+impl Tokenizer for NonWordBoundaries {
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        let chars = self.chars.clone();
+        split_runs(text, move |c| {
+            if c.is_alphanumeric() || chars.contains(&c) {
+                CharClass::Word
+            } else if c.is_whitespace() {
+                CharClass::Space
+            } else {
+                CharClass::Other
+            }
+        })
+    }
+}
+
+/// Same as `SimpleTokenizer`, but only the ASCII chars are classified (the others are part of
+/// the words), and the text is scanned byte by byte, for when it's known to be ASCII.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct AsciiTokenizer;
+
+impl Tokenizer for AsciiTokenizer {
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        let class = |byte: u8| {
+            if byte.is_ascii_alphanumeric() || !byte.is_ascii() {
+                CharClass::Word
+            } else if byte.is_ascii_whitespace() {
+                CharClass::Space
+            } else {
+                CharClass::Other
+            }
+        };
+        let bytes = text.as_bytes();
+        let mut start = 0;
+        std::iter::from_fn(move || {
+            let &first = bytes.get(start)?;
+            let first_class = class(first);
+            let mut end = start + 1;
+            if first_class != CharClass::Other {
+                // a non-ASCII char is never split, since all of its bytes are part of the words
+                while bytes.get(end).is_some_and(|&byte| class(byte) == first_class) {
+                    end += 1;
+                }
+            }
+            let token = (start, &text[start..end]);
+            start = end;
+            Some(token)
+        })
+    }
+}
+
+/// Same as `SimpleTokenizer`, but the word chars are the ones for which the predicate returns
+/// `true`, e.g. `|c: char| c.is_alphanumeric() || c == '#' || c == '@'` to keep the hashtags and
+/// the mentions whole.
+///
+/// The predicate is cloned for each text, so it should be cheap to clone (like a closure that
+/// doesn't capture anything).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct WordCharTokenizer<F>(pub F);
+
+impl<F> Tokenizer for WordCharTokenizer<F>
+where
+    F: Fn(char) -> bool + Clone + 'static,
+{
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        let is_word_char = self.0.clone();
+        split_runs(text, move |c| {
+            if is_word_char(c) {
+                CharClass::Word
+            } else if c.is_whitespace() {
+                CharClass::Space
+            } else {
+                CharClass::Other
+            }
+        })
+    }
+}
+
+/// How a char is grouped with its neighbours into tokens.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    /// Part of a run of word chars.
+    Word,
+    /// Part of a run of whitespace.
+    Space,
+    /// A token on its own.
+    Other,
+}
+
+/// Splits `text` into the runs of chars of the same class (and each `Other` char on its own).
+fn split_runs<'t>(
+    text: &'t str,
+    class: impl Fn(char) -> CharClass + 't,
+) -> impl Iterator<Item = (usize, &'t str)> + 't {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        let (start, c) = chars.next()?;
+        let c_class = class(c);
+        let mut end = start + c.len_utf8();
+        if c_class != CharClass::Other {
+            while let Some((idx, c)) = chars.next_if(|&(_, c)| class(c) == c_class) {
+                end = idx + c.len_utf8();
+            }
+        }
+        Some((start, &text[start..end]))
+    })
+}
+
+/// Splits the text into its chars, so that the keywords match anywhere, even inside a word
+/// (e.g. "cat" in "concatenate"), which is also how to match IDs and hashtags inside other
+/// text, or words in languages written without spaces (e.g. Chinese).
+///
+/// Each char is looked up in the trie, so it's slower than matching whole words.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CharTokenizer;
+
+impl Tokenizer for CharTokenizer {
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        text.char_indices().map(move |(idx, c)| (idx, &text[idx..idx + c.len_utf8()]))
+    }
+}
+
+/// Wraps a tokenizer, and drops the tokens that only contain punctuation (e.g. "," or "."),
+/// so that they don't have to match between the keywords and the text: "Hello, world!"
+/// matches "Hello world".
+///
+/// The initials of an abbreviation (single letters separated by dots) are joined into one
+/// token without the dots, so "U.S. Department of Justice" matches "US Department of Justice"
+/// (whether the tokenizer splits "U.S." into `["U", ".", "S", "."]` or into `["U.S", "."]`).
+/// The rest of the punctuation inside a token is kept, e.g. "can't" doesn't match "cant".
+/// The punctuation is the ASCII one and the "General Punctuation" Unicode block (e.g. "—"
+/// and "…").
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IgnorePunctuation<T = WordBoundTokenizer>(pub T);
+
+impl<T: Tokenizer> Tokenizer for IgnorePunctuation<T> {
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        let mut tokens = self.0.tokenize(text).peekable();
+        std::iter::from_fn(move || loop {
+            let (start, token) = tokens.next()?;
+            if token.chars().all(is_punctuation) {
+                continue;
+            }
+            let mut end = start + token.len();
+            if is_initials(token) {
+                // e.g. "U" followed by ".S", but not by ".Sc"
+                let mut rest = text[end..].chars();
+                while let (Some('.'), Some(c)) = (rest.next(), rest.next()) {
+                    let ends_word = !rest.clone().next().is_some_and(char::is_alphanumeric);
+                    if !c.is_alphabetic() || !ends_word {
+                        break;
+                    }
+                    end += 1 + c.len_utf8();
+                }
+                while tokens.next_if(|&(idx, _)| idx < end).is_some() {}
+            }
+            return Some((start, &text[start..end]));
+        })
+    }
+
+    #[inline]
+    fn normalize<'t>(&self, token: &'t str) -> Cow<'t, str> {
+        if token.contains('.') && is_initials(token) {
+            let joined: String = token.chars().filter(|&c| c != '.').collect();
+            return Cow::Owned(self.0.normalize(&joined).into_owned());
+        }
+        self.0.normalize(token)
+    }
+}
+
+/// Returns `true` if `token` is made of single letters separated by dots (e.g. "U.S" or "e").
+fn is_initials(token: &str) -> bool {
+    let mut chars = token.chars();
+    loop {
+        if !chars.next().is_some_and(char::is_alphabetic) {
+            return false;
+        }
+        match chars.next() {
+            None => return true,
+            Some('.') => {}
+            Some(_) => return false,
+        }
+    }
+}
+
+/// Wraps a tokenizer, and splits the tokens written in Chinese or Japanese (i.e. with Han,
+/// Hiragana or Katakana chars) into their chars, so that the keywords are found in a text
+/// without spaces, e.g. `WordBoundTokenizer` keeps the run of Katakana "タワー" together, so
+/// the keyword "ワー" wouldn't match it.
+///
+/// The other tokens (e.g. "iPhone" in "iPhoneを") are left as they are, and the chars are
+/// actually grapheme clusters, so a combining mark stays with its char.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CjkChars<T = WordBoundTokenizer>(pub T);
+
+impl<T: Tokenizer> Tokenizer for CjkChars<T> {
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        self.0.tokenize(text).flat_map(|(offset, token)| {
+            let is_cjk = token.chars().any(is_cjk);
+            let chars = is_cjk.then(|| token.grapheme_indices(true));
+            let whole = (!is_cjk).then_some((0, token));
+            let tokens = chars.into_iter().flatten().chain(whole);
+            tokens.map(move |(idx, token)| (offset + idx, token))
+        })
+    }
+
+    #[inline]
+    fn normalize<'t>(&self, token: &'t str) -> Cow<'t, str> {
+        self.0.normalize(token)
+    }
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}' // Hiragana and Katakana
+        | '\u{31F0}'..='\u{31FF}' // Katakana Phonetic Extensions
+        | '\u{3400}'..='\u{4DBF}' // CJK Unified Ideographs Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+        | '\u{FF66}'..='\u{FF9F}' // Halfwidth Katakana
+        | '\u{20000}'..='\u{3FFFF}' // the other extensions of the ideographs
+    )
+}
+
+/// Wraps a tokenizer, and separates the emoji from the other chars of their token, each emoji
+/// (with its modifiers, variation selectors and ZWJ sequence) becoming a token on its own.
+///
+/// `WordBoundTokenizer` already keeps the emoji sequences whole (e.g. "👨‍👩‍👧‍👦" or "👍🏽"), but a
+/// skin tone or a ZWJ right after a letter is joined with it (e.g. "a🏽b" is a single token),
+/// so the emoji keywords wouldn't match there.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SeparateEmoji<T = WordBoundTokenizer>(pub T);
+
+impl<T: Tokenizer> Tokenizer for SeparateEmoji<T> {
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        self.0.tokenize(text).flat_map(|(offset, token)| {
+            let has_emoji = token.chars().any(|c| is_emoji(c) || is_emoji_modifier(c));
+            let pieces = has_emoji.then(|| split_emoji(token));
+            let whole = (!has_emoji).then_some((0, token));
+            let tokens = pieces.into_iter().flatten().chain(whole);
+            tokens.map(move |(idx, token)| (offset + idx, token))
+        })
+    }
+
+    #[inline]
+    fn normalize<'t>(&self, token: &'t str) -> Cow<'t, str> {
+        self.0.normalize(token)
+    }
+}
+
+/// Splits `token` into its emoji (i.e. the grapheme clusters that contain one) and the runs of
+/// other chars between them.
+fn split_emoji(token: &str) -> Vec<(usize, &str)> {
+    let mut pieces = Vec::new();
+    // the start of the run of chars that aren't an emoji
+    let mut run_start = 0;
+    for (idx, grapheme) in token.grapheme_indices(true) {
+        // a modifier or a ZWJ is joined with the letter before it, so we split them again
+        let emoji_start = grapheme.find(|c| is_emoji(c) || is_emoji_modifier(c) || c == '\u{200D}');
+        if let Some(emoji_start) = emoji_start {
+            let emoji_idx = idx + emoji_start;
+            if run_start < emoji_idx {
+                pieces.push((run_start, &token[run_start..emoji_idx]));
+            }
+            pieces.push((emoji_idx, &token[emoji_idx..idx + grapheme.len()]));
+            run_start = idx + grapheme.len();
+        }
+    }
+    if run_start < token.len() {
+        pieces.push((run_start, &token[run_start..]));
+    }
+    pieces
+}
+
+/// Whether `c` is a pictographic emoji (approximately the `Extended_Pictographic` property).
+fn is_emoji(c: char) -> bool {
+    matches!(c,
+        '\u{A9}' | '\u{AE}' | '\u{203C}' | '\u{2049}' | '\u{2122}' | '\u{2139}'
+        | '\u{2194}'..='\u{21AA}'
+        | '\u{231A}'..='\u{23FF}'
+        | '\u{24C2}' | '\u{25AA}'..='\u{25FE}'
+        | '\u{2600}'..='\u{27BF}'
+        | '\u{2934}' | '\u{2935}' | '\u{2B05}'..='\u{2B55}'
+        | '\u{3030}' | '\u{303D}' | '\u{3297}' | '\u{3299}'
+        | '\u{1F000}'..='\u{1F1FF}' // the game pieces and the regional indicators (flags)
+        | '\u{1F300}'..='\u{1F3FA}'
+        | '\u{1F400}'..='\u{1FAFF}'
+    )
+}
+
+/// Whether `c` is a skin tone, which modifies the emoji before it.
+fn is_emoji_modifier(c: char) -> bool {
+    matches!(c, '\u{1F3FB}'..='\u{1F3FF}')
+}
+
+/// A Unicode normalization form, see [`Normalize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Normalization {
+    /// The canonical composition, e.g. "e" followed by the combining acute accent becomes "é".
+    Nfc,
+    /// The compatibility composition, which also replaces the chars that are only a variant
+    /// of others, e.g. "ﬁ" becomes "fi" and "①" becomes "1".
+    Nfkc,
+}
+
+/// Wraps a tokenizer, and normalizes each token to the Unicode normalization `form`, so that
+/// the same text matches however it's encoded, e.g. "café" with a precomposed "é" or with an
+/// "e" and a combining accent.
+///
+/// Each token is normalized on its own (and the spans are the ones of the original tokens),
+/// so the text must be split the same way before and after the normalization, which is the
+/// case with `WordBoundTokenizer`, since it keeps the combining marks with their char.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Normalize<T = WordBoundTokenizer> {
+    pub form: Normalization,
+    pub tokenizer: T,
+}
+
+impl<T> Normalize<T> {
+    pub fn new(form: Normalization, tokenizer: T) -> Self {
+        Self { form, tokenizer }
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for Normalize<T> {
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        self.tokenizer.tokenize(text)
+    }
+
+    fn normalize<'t>(&self, token: &'t str) -> Cow<'t, str> {
+        let token = self.tokenizer.normalize(token);
+        // most tokens are already normalized, which is much faster to check than to normalize
+        let is_normalized = match self.form {
+            Normalization::Nfc => is_nfc_quick(token.chars()),
+            Normalization::Nfkc => is_nfkc_quick(token.chars()),
+        };
+        if is_normalized == IsNormalized::Yes {
+            return token;
+        }
+        Cow::Owned(match self.form {
+            Normalization::Nfc => token.nfc().collect(),
+            Normalization::Nfkc => token.nfkc().collect(),
+        })
+    }
+}
+
+/// Wraps a tokenizer, and removes the diacritics (i.e. the accents) from the tokens, so that
+/// "café" and "cafe" match each other.
+///
+/// The chars are decomposed (NFD), and the combining marks are dropped, so the letters that
+/// aren't an accented version of another one (e.g. "ø" or "ł") are kept.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FoldDiacritics<T = WordBoundTokenizer>(pub T);
+
+impl<T: Tokenizer> Tokenizer for FoldDiacritics<T> {
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        self.0.tokenize(text)
+    }
+
+    fn normalize<'t>(&self, token: &'t str) -> Cow<'t, str> {
+        let token = self.0.normalize(token);
+        if token.is_ascii() {
+            return token;
+        }
+        Cow::Owned(token.nfd().filter(|&c| !is_combining_mark(c)).collect())
+    }
+}
+
+/// Wraps a tokenizer, and replaces each token with the result of `stem` (e.g. a stemmer or a
+/// lemmatizer), so that the keyword "run" matches "running" and "runs".
+///
+/// `stem` receives the tokens as they are normalized by `tokenizer` (but not lowercased by a
+/// case insensitive processor), including the whitespace and the punctuation.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Stem<F, T = WordBoundTokenizer> {
+    pub stem: F,
+    pub tokenizer: T,
+}
+
+impl<F, T> Stem<F, T> {
+    pub fn new(stem: F, tokenizer: T) -> Self {
+        Self { stem, tokenizer }
+    }
+}
+
+impl<F, T> Tokenizer for Stem<F, T>
+where
+    F: Fn(&str) -> String,
+    T: Tokenizer,
+{
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        self.tokenizer.tokenize(text)
+    }
+
+    fn normalize<'t>(&self, token: &'t str) -> Cow<'t, str> {
+        let token = self.tokenizer.normalize(token);
+        let stem = (self.stem)(&token);
+        if stem == token {
+            return token;
+        }
+        Cow::Owned(stem)
+    }
+}
+
+/// A language whose case folding differs from the default Unicode one, see [`LocaleCase`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Locale {
+    /// "I" is the uppercase of the dotless "ı", and "İ" of "i".
+    Turkish,
+    /// The same as Turkish.
+    Azerbaijani,
+    /// The lowercase "i" and "j" keep their dot (U+0307) under an accent, e.g. "Ì" is "i̇̀".
+    Lithuanian,
+}
+
+/// Wraps a tokenizer, and applies the case rules of `locale` to the tokens, so that a case
+/// insensitive processor matches e.g. the Turkish "ılık" with "ILIK" (and not with "ilik").
+///
+/// It only maps the chars that are folded differently in `locale`, and the rest of the case
+/// folding is still done by the processor, so it's meant for `CaseMode::Insensitive`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LocaleCase<T = WordBoundTokenizer> {
+    pub locale: Locale,
+    pub tokenizer: T,
+}
+
+impl<T> LocaleCase<T> {
+    pub fn new(locale: Locale, tokenizer: T) -> Self {
+        Self { locale, tokenizer }
+    }
+}
+
+impl<T: Tokenizer> Tokenizer for LocaleCase<T> {
+    #[inline]
+    fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+        self.tokenizer.tokenize(text)
+    }
+
+    fn normalize<'t>(&self, token: &'t str) -> Cow<'t, str> {
+        let token = self.tokenizer.normalize(token);
+        match self.locale {
+            Locale::Turkish | Locale::Azerbaijani => {
+                if !token.contains(['I', 'İ']) {
+                    return token;
+                }
+                // "İ" can also be written as "I" followed by a combining dot above
+                let token = token.replace("I\u{307}", "i");
+                Cow::Owned(token.replace('I', "ı").replace('İ', "i"))
+            }
+            Locale::Lithuanian => {
+                if token.is_ascii() {
+                    return token;
+                }
+                // the accents are compared decomposed, without the dot that "i" keeps under them
+                let mut folded = String::with_capacity(token.len());
+                for c in token.nfd() {
+                    let after_i = folded.ends_with(['i', 'j', 'I', 'J', '\u{328}']);
+                    if !(c == '\u{307}' && after_i) {
+                        folded.push(c);
+                    }
+                }
+                Cow::Owned(folded)
+            }
+        }
+    }
+}
+
+fn is_punctuation(c: char) -> bool {
+    c.is_ascii_punctuation() || ('\u{2010}'..='\u{205E}').contains(&c)
+}
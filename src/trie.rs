@@ -0,0 +1,531 @@
+use std::sync::Arc;
+use unicase::UniCase;
+use crate::children::Children;
+use crate::key::Key;
+use crate::CaseMode;
+
+/// The index of a node in the arena of its `Trie`.
+pub(crate) type NodeId = u32;
+
+/// The root is always the first node of the arena.
+pub(crate) const ROOT: NodeId = 0;
+
+#[derive(Clone, Debug)]
+pub(crate) struct Node<V> {
+    pub value: Option<V>,
+    pub priority: u32,
+    pub children: Children,
+}
+
+// not derived, because that would require `V: Default`
+impl<V> Default for Node<V> {
+    fn default() -> Self {
+        Self {
+            value: None,
+            priority: 0,
+            children: Default::default(),
+        }
+    }
+}
+
+impl<V> Node<V> {
+    /// Sets the value of the node (and resets its priority), returns `true` if the node wasn't
+    /// a keyword until now.
+    pub fn set_value(&mut self, value: V) -> bool {
+        // even if there is already a keyword, the user can still overwrite its value,
+        // but the length should only be incremented if the keyword isn't already there
+        self.priority = 0;
+        self.value.replace(value).is_none()
+    }
+}
+
+/// The number of children of the root whose (normalized) token can start with each byte, so
+/// most of the tokens of a text, that can't start any keyword, skip the lookup in the hashmap.
+#[derive(Clone, Debug)]
+struct FirstBytes([u32; 256]);
+
+impl Default for FirstBytes {
+    fn default() -> Self {
+        Self([0; 256])
+    }
+}
+
+impl FirstBytes {
+    /// Returns the bytes that a token of a text can start with to match `key`, where the same
+    /// byte can be repeated (so it's counted twice, and removed twice).
+    fn of(key: &Key) -> impl Iterator<Item = u8> {
+        let token = key.as_str();
+        let bytes = match (token.as_bytes().first(), key.case_mode()) {
+            (None, _) => vec![],
+            (Some(&b), CaseMode::Sensitive) => vec![b],
+            (Some(&b), _) if b.is_ascii() => vec![b.to_ascii_lowercase(), b.to_ascii_uppercase()],
+            (Some(&b), CaseMode::AsciiInsensitive) => vec![b],
+            // a non-ASCII char can fold into ASCII ones (e.g. "ẞ" into "ss", or the Kelvin sign
+            // into "k"), while the tokens that start with a non-ASCII byte are never skipped
+            (Some(_), CaseMode::Insensitive) => {
+                let folded = UniCase::unicode(token).to_folded_case();
+                match folded.as_bytes().first() {
+                    Some(&b) if b.is_ascii() => {
+                        vec![b.to_ascii_lowercase(), b.to_ascii_uppercase()]
+                    }
+                    _ => vec![],
+                }
+            }
+        };
+        bytes.into_iter()
+    }
+
+    fn add(&mut self, key: &Key) {
+        for b in Self::of(key) {
+            self.0[b as usize] += 1;
+        }
+    }
+
+    fn remove(&mut self, key: &Key) {
+        for b in Self::of(key) {
+            self.0[b as usize] -= 1;
+        }
+    }
+
+    /// Returns `false` if no child of the root can match the (normalized) token.
+    #[inline]
+    fn may_match(&self, token: &str, case_mode: CaseMode) -> bool {
+        match token.as_bytes().first() {
+            None => true,
+            Some(&b) if !b.is_ascii() && case_mode == CaseMode::Insensitive => true,
+            Some(&b) => self.0[b as usize] != 0,
+        }
+    }
+}
+
+/// Past this many children of the root, `FirstTokens` is used (otherwise `FirstBytes` already
+/// skips most of the tokens, and the lookup is cheap anyway).
+const MIN_FIRST_TOKENS: usize = 64;
+
+/// A bitset of the hashes of the (case folded) tokens of the children of the root, so the
+/// tokens that pass `FirstBytes` can still skip the lookup in the hashmap of the root, which
+/// is big (and mostly out of the cache) when there are many keywords.
+///
+/// Each token sets two bits, and there are at least 8 bits per token, so about 5% of the
+/// tokens that can't start any keyword still go through the lookup. The bits of the removed
+/// tokens aren't cleared (which only lets a few more tokens through) until it's rebuilt.
+#[derive(Clone, Debug, Default)]
+struct FirstTokens {
+    // empty until the root has more than `MIN_FIRST_TOKENS` children
+    bits: Vec<u64>,
+    // the number of tokens that were added since the bitset was built
+    len: usize,
+}
+
+impl FirstTokens {
+    /// Returns the hash of the case folded token, or `None` if the token can't be filtered.
+    #[inline]
+    fn hash(token: &str, case_mode: CaseMode) -> Option<u64> {
+        // the same mixing as `FxHasher`
+        let mut hash = 0u64;
+        let mut add = |b: u8| {
+            hash = (hash.rotate_left(5) ^ b as u64).wrapping_mul(0x517c_c1b7_2722_0a95);
+        };
+        match case_mode {
+            CaseMode::Sensitive => token.bytes().for_each(add),
+            // a non-ASCII token can match a key with another length (e.g. "ß" and "ss")
+            CaseMode::Insensitive if !token.is_ascii() => return None,
+            _ => token.bytes().for_each(|b| add(b.to_ascii_lowercase())),
+        }
+        // the bits are taken from both halves of the hash, so they must all be mixed
+        hash ^= hash >> 33;
+        hash = hash.wrapping_mul(0xff51_afd7_ed55_8ccd);
+        Some(hash ^ (hash >> 33))
+    }
+
+    /// Returns the hash of the tokens that match `key`, or `None` if only the tokens that
+    /// aren't filtered can match it.
+    fn key_hash(key: &Key) -> Option<u64> {
+        match key.case_mode() {
+            CaseMode::Insensitive if !key.as_str().is_ascii() => {
+                // e.g. "Maße" is matched by "MASSE", whose folded form is the same
+                let folded = UniCase::unicode(key.as_str()).to_folded_case();
+                Self::hash(&folded, CaseMode::Insensitive)
+            }
+            case_mode => Self::hash(key.as_str(), case_mode),
+        }
+    }
+
+    /// Returns the two bits of the hash.
+    #[inline]
+    fn bits(&self, hash: u64) -> [usize; 2] {
+        let mask = self.bits.len() * 64 - 1;
+        [hash as usize & mask, (hash >> 32) as usize & mask]
+    }
+
+    /// Adds a child of the root, returns `true` if the bitset should be rebuilt to have room
+    /// for more.
+    fn add(&mut self, key: &Key) -> bool {
+        self.len += 1;
+        if !self.bits.is_empty() {
+            if let Some(hash) = Self::key_hash(key) {
+                for bit in self.bits(hash) {
+                    self.bits[bit / 64] |= 1 << (bit % 64);
+                }
+            }
+        }
+        self.len > (self.bits.len() * 8).max(MIN_FIRST_TOKENS)
+    }
+
+    /// Rebuilds the bitset from the children of the root.
+    fn rebuild<'a>(&mut self, keys: impl ExactSizeIterator<Item = &'a Key>) {
+        let len = keys.len();
+        *self = Self::default();
+        if len > MIN_FIRST_TOKENS {
+            // 16 bits per token, so that it can double before the next rebuild
+            self.bits = vec![0; (len * 16).next_power_of_two() / 64];
+        }
+        for key in keys {
+            self.add(key);
+        }
+    }
+
+    /// Returns `false` if no child of the root can match the (normalized) token.
+    #[inline]
+    fn may_match(&self, token: &str, case_mode: CaseMode) -> bool {
+        if self.bits.is_empty() {
+            return true;
+        }
+        let Some(hash) = Self::hash(token, case_mode) else {
+            return true;
+        };
+        self.bits(hash).into_iter().all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+}
+
+/// A trie of tokens, whose nodes are all stored in a single vector (an arena), where they refer
+/// to their children by index.
+///
+/// Unlike nesting the children inside the hashmap of their parent, growing a hashmap doesn't
+/// move the whole subtrees of its children, and dropping (or cloning) the trie is a single
+/// pass over the vector, instead of a recursive walk through all the hashmaps.
+#[derive(Clone, Debug)]
+pub(crate) struct Trie<V> {
+    nodes: Vec<Node<V>>,
+    // the slots of the nodes that were removed, which are reused before growing the arena
+    free: Vec<NodeId>,
+    first_bytes: FirstBytes,
+    first_tokens: FirstTokens,
+    // the distinct tokens of the keys, so a token that is in many keywords (e.g. "the" or "of")
+    // is only stored once, and shared by all its keys
+    tokens: std::collections::HashSet<Arc<str>, fxhash::FxBuildHasher>,
+}
+
+impl<V> Default for Trie<V> {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+impl<V> Trie<V> {
+    /// Creates an empty trie with room for `capacity` children of the root.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let root = Node {
+            value: None,
+            priority: 0,
+            children: Children::with_capacity(capacity),
+        };
+        Self {
+            nodes: vec![root],
+            free: Vec::new(),
+            first_bytes: FirstBytes::default(),
+            first_tokens: FirstTokens::default(),
+            tokens: Default::default(),
+        }
+    }
+
+    pub fn root(&self) -> NodeRef<'_, V> {
+        self.get(ROOT)
+    }
+
+    pub fn get(&self, id: NodeId) -> NodeRef<'_, V> {
+        NodeRef { trie: self, id }
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node<V> {
+        &self.nodes[id as usize]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node<V> {
+        &mut self.nodes[id as usize]
+    }
+
+    /// Returns the number of nodes (not counting the root).
+    pub fn node_count(&self) -> usize {
+        self.nodes.len() - self.free.len() - 1
+    }
+
+    /// Removes all the nodes, but keeps the capacity of the root.
+    pub fn clear(&mut self) {
+        self.nodes.truncate(1);
+        self.free.clear();
+        self.first_bytes = FirstBytes::default();
+        self.first_tokens = FirstTokens::default();
+        self.tokens.clear();
+        let root = self.node_mut(ROOT);
+        root.value = None;
+        root.priority = 0;
+        root.children.clear();
+    }
+
+    /// Returns the key of the (normalized) token, whose string is shared with the other keys of
+    /// the same token.
+    pub fn key(&mut self, token: &str, case_mode: CaseMode) -> Key {
+        let token = match self.tokens.get(token) {
+            Some(token) => token.clone(),
+            None => {
+                let token: Arc<str> = token.into();
+                self.tokens.insert(token.clone());
+                token
+            }
+        };
+        Key::new(token, case_mode)
+    }
+
+    /// Same as `NodeRef::key_child()`, but returns the index of the child.
+    pub fn child_id(&self, id: NodeId, token: &str, case_mode: CaseMode) -> Option<NodeId> {
+        self.get(id).key_child(token, case_mode).map(|child| child.id)
+    }
+
+    /// Returns the child of `key`, inserting an empty one if it's missing.
+    ///
+    /// # Panics
+    ///
+    /// If there are more than `u32::MAX` nodes.
+    pub fn child_or_insert(&mut self, id: NodeId, key: Key) -> NodeId {
+        // the key is only looked up once, so we insert the index the new node would get
+        let next_id = match self.free.last() {
+            Some(&free_id) => free_id,
+            None => NodeId::try_from(self.nodes.len()).expect("too many nodes"),
+        };
+        let (first_bytes, first_tokens) = (&mut self.first_bytes, &mut self.first_tokens);
+        let mut is_full = false;
+        let child = self.nodes[id as usize].children.get_or_insert(key, next_id, |key| {
+            if id == ROOT {
+                first_bytes.add(key);
+                is_full = first_tokens.add(key);
+            }
+        });
+        if is_full {
+            self.rebuild_first_tokens();
+        }
+        if child == next_id {
+            match self.free.pop() {
+                Some(_) => self.nodes[next_id as usize] = Node::default(),
+                None => self.nodes.push(Node::default()),
+            }
+        }
+        child
+    }
+
+    /// Removes the child of the (normalized) token, and all its descendants.
+    pub fn remove_child(&mut self, id: NodeId, token: &str, case_mode: CaseMode) {
+        if let Some((key, child)) = self.node_mut(id).children.remove(token, case_mode) {
+            if id == ROOT {
+                self.first_bytes.remove(&key);
+            }
+            self.free_subtree(child);
+        }
+    }
+
+    /// Releases the slots of the node and of all its descendants (which must not be referenced
+    /// by their parent anymore).
+    fn free_subtree(&mut self, id: NodeId) {
+        let mut stack = vec![id];
+        while let Some(id) = stack.pop() {
+            let node = std::mem::take(self.node_mut(id));
+            stack.extend(node.children.iter().map(|(_, child)| child));
+            self.free.push(id);
+        }
+    }
+
+    /// Removes the nodes that don't lead to any keyword.
+    pub fn prune(&mut self) {
+        self.prune_children(ROOT);
+    }
+
+    /// Removes the children that don't lead to any keyword, returns `true` if this node doesn't
+    /// either.
+    fn prune_children(&mut self, id: NodeId) -> bool {
+        let children: Vec<_> = self.node(id).children.iter().map(|(_, child)| child).collect();
+        let mut dead: Vec<_> = children.into_iter().filter(|&c| self.prune_children(c)).collect();
+        if !dead.is_empty() {
+            dead.sort_unstable();
+            let first_bytes = &mut self.first_bytes;
+            self.nodes[id as usize].children.retain(|key, child| {
+                let is_dead = dead.binary_search(&child).is_ok();
+                if is_dead && id == ROOT {
+                    first_bytes.remove(key);
+                }
+                !is_dead
+            });
+            self.free.extend(dead);
+        }
+        let node = self.node(id);
+        node.value.is_none() && node.children.is_empty()
+    }
+
+    /// Releases the spare capacity of the arena and of the children of all the nodes, and the
+    /// tokens that aren't in any key anymore.
+    ///
+    /// If some nodes were removed, the arena is also repacked without their slots.
+    pub fn shrink_to_fit(&mut self) {
+        if !self.free.is_empty() {
+            self.repack();
+        }
+        for node in &mut self.nodes {
+            node.children.shrink_to_fit();
+        }
+        self.nodes.shrink_to_fit();
+        self.free.shrink_to_fit();
+        // without the bits of the removed tokens
+        self.rebuild_first_tokens();
+        // only the set holds them
+        self.tokens.retain(|token| Arc::strong_count(token) > 1);
+        self.tokens.shrink_to_fit();
+    }
+
+    fn rebuild_first_tokens(&mut self) {
+        let keys = self.nodes[ROOT as usize].children.iter().map(|(key, _)| key);
+        self.first_tokens.rebuild(keys);
+    }
+
+    /// Moves the nodes into a new arena without free slots, in depth-first order (so the
+    /// nodes of a keyword are mostly next to each other), and renumbers the children.
+    fn repack(&mut self) {
+        let mut order = Vec::with_capacity(self.nodes.len() - self.free.len());
+        let mut stack = vec![ROOT];
+        while let Some(id) = stack.pop() {
+            order.push(id);
+            stack.extend(self.node(id).children.iter().map(|(_, child)| child));
+        }
+        let mut new_ids = vec![ROOT; self.nodes.len()];
+        for (new_id, &id) in order.iter().enumerate() {
+            new_ids[id as usize] = new_id as NodeId;
+        }
+        let mut nodes: Vec<_> = order.iter().map(|&id| std::mem::take(self.node_mut(id))).collect();
+        for node in &mut nodes {
+            node.children.map_ids(|id| new_ids[id as usize]);
+        }
+        self.nodes = nodes;
+        self.free.clear();
+    }
+
+    /// Returns an estimate of the heap memory (in bytes) used by the trie, where `value_size`
+    /// returns the heap memory owned by a value.
+    pub fn memory_usage_with(&self, value_size: impl Fn(&V) -> usize) -> usize {
+        // the root isn't counted, just like the rest of the processor
+        let mut bytes = (self.nodes.capacity() - 1) * std::mem::size_of::<Node<V>>();
+        bytes += self.free.capacity() * std::mem::size_of::<NodeId>();
+        bytes += self.first_tokens.bits.capacity() * std::mem::size_of::<u64>();
+        // each bucket of the set holds a pointer, plus a control byte, and each token is
+        // allocated with its two reference counts
+        bytes += self.tokens.capacity() * (std::mem::size_of::<Arc<str>>() + 1);
+        let counts = 2 * std::mem::size_of::<usize>();
+        bytes += self.tokens.iter().map(|token| token.len() + counts).sum::<usize>();
+        for node in &self.nodes {
+            bytes += node.children.memory_usage();
+            bytes += node.value.as_ref().map_or(0, &value_size);
+        }
+        bytes
+    }
+}
+
+/// Two tries are equal if they have the same keywords (with the same values and priorities),
+/// regardless of where their nodes are in the arena.
+impl<V: PartialEq> PartialEq for Trie<V> {
+    fn eq(&self, other: &Self) -> bool {
+        let mut stack = vec![(self.root(), other.root())];
+        while let Some((node, other_node)) = stack.pop() {
+            let (node, other_node) = (node.node(), other_node.node());
+            if node.value != other_node.value
+                || node.priority != other_node.priority
+                || node.children.len() != other_node.children.len()
+            {
+                return false;
+            }
+            for (key, child) in node.children.iter() {
+                let Some((_, other_child)) = other_node.children.get(key.as_str(), key.case_mode())
+                else {
+                    return false;
+                };
+                stack.push((self.get(child), other.get(other_child)));
+            }
+        }
+        true
+    }
+}
+
+/// A node of a `Trie`, which can be traversed just like a reference to it.
+pub(crate) struct NodeRef<'a, V> {
+    trie: &'a Trie<V>,
+    id: NodeId,
+}
+
+// not derived, because that would require `V: Copy`
+impl<V> Clone for NodeRef<'_, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for NodeRef<'_, V> {}
+
+impl<'a, V> NodeRef<'a, V> {
+    fn node(self) -> &'a Node<V> {
+        self.trie.node(self.id)
+    }
+
+    pub fn value(self) -> Option<&'a V> {
+        self.node().value.as_ref()
+    }
+
+    pub fn priority(self) -> u32 {
+        self.node().priority
+    }
+
+    pub fn children(self) -> impl ExactSizeIterator<Item = (&'a Key, Self)> {
+        let trie = self.trie;
+        self.node().children.iter().map(move |(key, id)| (key, trie.get(id)))
+    }
+
+    pub fn has_children(self) -> bool {
+        !self.node().children.is_empty()
+    }
+
+    /// Returns the child of the token, which is already normalized (e.g. a key of a trie).
+    #[inline]
+    pub fn key_child(self, token: &str, case_mode: CaseMode) -> Option<Self> {
+        self.key_child_with_key(token, case_mode).map(|(_, child)| child)
+    }
+
+    /// Same as `key_child()`, but also returns the key of the child, i.e. the normalized token
+    /// it was inserted with.
+    #[inline]
+    pub fn key_child_with_key(self, token: &str, case_mode: CaseMode) -> Option<(&'a Key, Self)> {
+        if self.id == ROOT
+            && !(self.trie.first_bytes.may_match(token, case_mode)
+                && self.trie.first_tokens.may_match(token, case_mode))
+        {
+            return None;
+        }
+        let (key, id) = self.node().children.get(token, case_mode)?;
+        Some((key, self.trie.get(id)))
+    }
+
+    /// Returns the number of keywords in the subtree of this node (including itself).
+    pub fn keyword_count(self) -> usize {
+        let mut count = 0;
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            count += node.value().is_some() as usize;
+            stack.extend(node.children().map(|(_, child)| child));
+        }
+        count
+    }
+}
@@ -1,9 +1,14 @@
-use flashtext2::{KeywordProcessor, KeywordSpan};
+use flashtext2::{
+    case_insensitive, case_sensitive, CaseMode, KeywordProcessor, KeywordProcessorBuilder,
+};
+use std::ops::ControlFlow;
 
 #[test]
 fn test_from_strings() {
     let empty_slice: &[&str] = [].as_slice();
-    assert_eq!(KeywordProcessor::from(empty_slice), KeywordProcessor::new(false));
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(empty_slice.iter().copied());
+    assert_eq!(kp, case_insensitive::KeywordProcessor::new());
 
     let arrays = [
         ["hello"].as_slice(),
@@ -11,11 +16,12 @@ fn test_from_strings() {
     ];
 
     for slice in arrays {
-        let kp_from_arr = KeywordProcessor::from(slice);
+        let mut kp_from_arr = case_insensitive::KeywordProcessor::new();
+        kp_from_arr.add_keywords_from_iter(slice.iter().copied());
 
-        let mut kp = KeywordProcessor::new(false);
-        for word in slice {
-            kp.add_keyword(word, word);
+        let mut kp = case_insensitive::KeywordProcessor::new();
+        for &word in slice {
+            kp.add_keyword_with_clean_word(word, word);
         }
         assert_eq!(kp, kp_from_arr);
     }
@@ -24,8 +30,10 @@ fn test_from_strings() {
 
 #[test]
 fn test_from_tuples() {
-    let empty_slice: &[&str] = [].as_slice();
-    assert_eq!(KeywordProcessor::from(empty_slice), KeywordProcessor::new(false));
+    let empty_slice: &[(&str, &str)] = [].as_slice();
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_with_clean_word_from_iter(empty_slice.iter().copied());
+    assert_eq!(kp, case_insensitive::KeywordProcessor::new());
 
     let arrays = [
         [("hello", "Hello!")].as_slice(),
@@ -33,51 +41,2752 @@ fn test_from_tuples() {
     ];
 
     for slice in arrays {
-        let kp_from_arr = KeywordProcessor::from(slice);
+        let mut kp_from_arr = case_insensitive::KeywordProcessor::new();
+        kp_from_arr.add_keywords_with_clean_word_from_iter(slice.iter().copied());
 
-        let mut kp = KeywordProcessor::new(false);
-        for (word, clean_word) in slice {
-            kp.add_keyword(word, clean_word);
+        let mut kp = case_insensitive::KeywordProcessor::new();
+        for &(word, clean_word) in slice {
+            kp.add_keyword_with_clean_word(word, clean_word);
         }
         assert_eq!(kp, kp_from_arr);
     }
 }
 
 
+#[test]
+fn test_from_iterator() {
+    let empty_slice: &[&str] = [].as_slice();
+    let kp: case_insensitive::KeywordProcessor = empty_slice.iter().collect();
+    assert_eq!(kp, case_insensitive::KeywordProcessor::new());
+    let empty_slice: &[(&str, &str)] = [].as_slice();
+    let kp: case_insensitive::KeywordProcessor = empty_slice.iter().copied().collect();
+    assert_eq!(kp, case_insensitive::KeywordProcessor::new());
+
+    let words = ["py", "Python", "I love python!"];
+    let kp_from_iter: case_insensitive::KeywordProcessor = words.iter().copied().collect();
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(words);
+    assert_eq!(kp, kp_from_iter);
+
+    let pairs = [("py", "Python"), ("python", "Python"), ("py3", "Python 3.0")];
+    let kp_from_iter: case_insensitive::KeywordProcessor = pairs.iter().copied().collect();
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_with_clean_word_from_iter(pairs);
+    assert_eq!(kp, kp_from_iter);
+}
+
+
 #[test]
 fn test_extractor() {
-    struct TestCase<'a> {
-        words: &'a [(&'a str, &'a str)],
-        input_text: &'a str,
-        case_sensitive_output: &'a[&'a str],
-        case_insensitive_output: &'a[&'a str],
-        span_output: &'a[&'a KeywordSpan],
-        replace_keywords_output: &'a str,
-    }
-
-    // let cases = [
-    //     TestCase {
-    //         words: [("hello", "hello")].as_ref(),
-    //         input_text: "hello world",
-    //         case_sensitive_output: "",
-    //         case_insensitive_output: "",
-    //         span_output: "",
-    //         replace_keywords_output: "",
-    //     },
-    // ];
     // TODO: add a few dozen cases ...
 
     // case-insensitive
-    let mut kp = KeywordProcessor::new(false);
-    kp.add_keyword("Hello", "Hello");
-    assert_eq!(kp.extract_keywords("hello")[0], "Hello");
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keyword("Hello");
+    assert_eq!(kp.extract_keywords("hello").collect::<Vec<_>>(), ["Hello"]);
 
     // case-sensitive
-    let mut kp = KeywordProcessor::new(true);
-    kp.add_keyword("Hello", "Hello");
-    assert!(kp.extract_keywords("hello").is_empty());
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keyword("Hello");
+    assert_eq!(kp.extract_keywords("hello").count(), 0);
+
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keyword("Hello");
+    assert_eq!(kp.extract_keywords("Hello").collect::<Vec<_>>(), ["Hello"]);
+}
+
+
+#[test]
+fn test_remove_keyword() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New", "New York", "New York City"]);
+    assert_eq!(kp.len(), 3);
+
+    // a keyword that is a prefix of other keywords
+    assert!(kp.remove_keyword("New York"));
+    assert_eq!(kp.len(), 2);
+    assert_eq!(
+        kp.extract_keywords("New York City, New York").collect::<Vec<_>>(),
+        ["New York City", "New"]
+    );
+
+    // the keyword isn't there anymore
+    assert!(!kp.remove_keyword("New York"));
+    // never was there
+    assert!(!kp.remove_keyword("Boston"));
+    assert!(!kp.remove_keyword("New York City, USA"));
+    assert_eq!(kp.len(), 2);
+
+    // removing the longest keyword must not affect its prefix
+    assert!(kp.remove_keyword(&String::from("New York City")));
+    assert_eq!(kp.extract_keywords("New York City").collect::<Vec<_>>(), ["New"]);
+
+    // once the trie is empty, all the nodes should have been pruned
+    assert!(kp.remove_keyword("New"));
+    assert!(kp.is_empty());
+    assert_eq!(kp, case_sensitive::KeywordProcessor::new());
+}
+
+
+#[test]
+fn test_remove_keyword_case_insensitive() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["Hello", "Hello World"]);
+
+    assert!(kp.remove_keyword("hello WORLD"));
+    assert!(!kp.remove_keyword("Hello World"));
+    assert_eq!(kp.extract_keywords("HELLO world").collect::<Vec<_>>(), ["Hello"]);
+
+    assert!(kp.remove_keyword("HELLO"));
+    assert_eq!(kp, case_insensitive::KeywordProcessor::new());
+}
+
+
+#[test]
+fn test_contains_and_get_clean_word() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keyword_with_clean_word("py", "Python");
+    kp.add_keyword("New York City");
+
+    assert!(kp.contains("py"));
+    assert_eq!(kp.get_clean_word("py"), Some("Python"));
+    assert!(kp.contains(&String::from("New York City")));
+    assert_eq!(kp.get_clean_word("New York City"), Some("New York City"));
+
+    // a prefix of a keyword isn't a keyword
+    assert!(!kp.contains("New York"));
+    assert_eq!(kp.get_clean_word("New York"), None);
+    assert!(!kp.contains("New York City!"));
+    assert!(!kp.contains("PY"));
+    assert!(!kp.contains(""));
+
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keyword_with_clean_word("py", "Python");
+    kp.add_keyword("New York City");
+
+    assert!(kp.contains("PY"));
+    assert_eq!(kp.get_clean_word("Py"), Some("Python"));
+    assert_eq!(kp.get_clean_word("new york CITY"), Some("New York City"));
+    assert!(!kp.contains("new york"));
+    assert_eq!(kp.get_clean_word("NEW"), None);
+}
+
+
+#[test]
+fn test_keywords() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    assert_eq!(kp.keywords().count(), 0);
+
+    kp.add_keywords_with_clean_word_from_iter([
+        ("New York", "NY"),
+        ("New York City", "NYC"),
+        ("New", "New"),
+        ("Hello, world!", "Hello"),
+        ("Hello", "Hello"),
+    ]);
+    kp.add_keyword("New"); // already there
+
+    let iter = kp.keywords_with_clean_word();
+    assert_eq!(iter.len(), kp.len());
+    let mut keywords: Vec<_> = iter.collect();
+    keywords.sort();
+    assert_eq!(keywords.len(), kp.len());
+    assert_eq!(
+        keywords,
+        [
+            ("Hello".to_string(), "Hello"),
+            ("Hello, world!".to_string(), "Hello"),
+            ("New".to_string(), "New"),
+            ("New York".to_string(), "NY"),
+            ("New York City".to_string(), "NYC"),
+        ]
+    );
+
+    let mut keywords: Vec<_> = kp.keywords().collect();
+    keywords.sort();
+    assert_eq!(keywords, ["Hello", "Hello, world!", "New", "New York", "New York City"]);
+
+    // the keywords must round-trip into an identical processor
+    let mut kp2 = case_sensitive::KeywordProcessor::new();
+    for (word, clean_word) in kp.keywords_with_clean_word() {
+        assert_eq!(kp.get_clean_word(&word), Some(clean_word));
+        kp2.add_keyword_with_clean_word(&word, clean_word);
+    }
+    assert_eq!(kp, kp2);
+
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New  York", "new york", "Foo"]);
+    let mut keywords: Vec<_> = kp.keywords().collect();
+    keywords.sort();
+    assert_eq!(keywords.len(), kp.len());
+    // the tokens keep the casing they were first inserted with
+    assert_eq!(keywords, ["Foo", "New  York", "New york"]);
+}
+
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_round_trip() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keywords_with_clean_word_from_iter([
+        ("py", "Python"),
+        ("New York", "New York"),
+        ("New York City", "NYC"),
+        ("Hello, world!", "Hello"),
+    ]);
+    let json = serde_json::to_string(&kp).unwrap();
+    let kp2: case_sensitive::KeywordProcessor = serde_json::from_str(&json).unwrap();
+    assert_eq!(kp, kp2);
+    assert_eq!(kp.len(), kp2.len());
+
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["Foo", "foo bar", "BAZ"]);
+    let json = serde_json::to_string(&kp).unwrap();
+    let kp2: case_insensitive::KeywordProcessor = serde_json::from_str(&json).unwrap();
+    assert_eq!(kp, kp2);
+    assert_eq!(kp2.extract_keywords("FOO BAR baz").collect::<Vec<_>>(), ["foo bar", "BAZ"]);
+
+    let kp: case_sensitive::KeywordProcessor = serde_json::from_str("[]").unwrap();
+    assert!(kp.is_empty());
+
+    let mut kp = case_sensitive::KeywordProcessor::<u32>::default();
+    kp.add_keyword_with_value("py", 1);
+    let json = serde_json::to_string(&kp).unwrap();
+    assert_eq!(json, r#"[["py",1]]"#);
+    assert_eq!(serde_json::from_str::<case_sensitive::KeywordProcessor<u32>>(&json).unwrap(), kp);
+}
+
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_serde_drops_replacements() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keyword_with_replacement("rust", "Rust", "[Rust](https://www.rust-lang.org)");
+    assert_eq!(kp.replace_keywords("I love rust"), "I love [Rust](https://www.rust-lang.org)");
+
+    let json = serde_json::to_string(&kp).unwrap();
+    assert_eq!(json, r#"[["rust","Rust"]]"#);
+    let kp: case_sensitive::KeywordProcessor = serde_json::from_str(&json).unwrap();
+    // only the clean word is left
+    assert_eq!(kp.replace_keywords("I love rust"), "I love Rust");
+}
+
+
+#[test]
+fn test_owned_keywords() {
+    fn build_processor(lines: &str) -> case_sensitive::KeywordProcessor {
+        // the keywords are dropped at the end of the function, the processor doesn't borrow them
+        let keywords: Vec<(String, String)> = lines
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(word, clean_word)| (word.to_string(), clean_word.to_string()))
+            .collect();
+
+        let mut kp = case_sensitive::KeywordProcessor::new();
+        kp.add_keywords_with_clean_word_from_iter(keywords);
+        kp
+    }
+
+    fn assert_static<T: 'static>(_: &T) {}
+
+    let mut kp = build_processor("py=Python\nNew York=NY\nrs=Rust");
+    assert_static(&kp);
+    assert_eq!(kp.len(), 3);
+
+    // the owned strings are moved into the processor
+    let word = String::from("Rust");
+    kp.add_keyword(word);
+    kp.add_keyword_with_clean_word("rust", "rs".repeat(2));
+    let word = String::from("go");
+    kp.add_keyword(&word);
+    assert_eq!(word, "go");
+    assert_eq!(kp.len(), 6);
+    assert_eq!(kp.get_clean_word("rust"), Some("rsrs"));
+    assert!(kp.remove_keyword("Rust") && kp.remove_keyword("rust") && kp.remove_keyword("go"));
+
+    for _ in 0..2 {
+        let text = String::from("I love py and rs, in New York");
+        let found: Vec<_> = kp.extract_keywords_with_span(&text).collect();
+        assert_eq!(found, [("Python", 7, 9), ("Rust", 14, 16), ("NY", 21, 29)]);
+    }
+}
+
+
+#[cfg(feature = "rayon")]
+#[test]
+fn test_extract_keywords_par() {
+    // a simple LCG, so the text is random-ish but the test is deterministic
+    let mut seed = 42_u64;
+    let mut next_word = |words: &[&'static str]| {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        words[(seed >> 33) as usize % words.len()]
+    };
+    let vocabulary = ["New", "York", "City", "is", "big", "the", "a", "of", "x"];
+    let separators = [" ", " ", " ", ", ", "\n"];
+    let mut text = String::new();
+    for _ in 0..100_000 {
+        text += next_word(&vocabulary);
+        text += next_word(&separators);
+    }
+    let keywords = [
+        "New York",
+        "New York City",
+        "York",
+        "City is big",
+        "a",
+        "the big x",
+        "New York City is big, the City of New York is big",
+        "x x x x x x x x x x",
+    ];
+
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(keywords);
+    let expected: Vec<_> = kp.extract_keywords_with_span(&text).collect();
+    assert!(expected.len() > 1000);
+    assert_eq!(kp.extract_keywords_par(&text), expected);
+
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(keywords);
+    let text = text.to_uppercase();
+    let expected: Vec<_> = kp.extract_keywords_with_span(&text).collect();
+    assert_eq!(kp.extract_keywords_par(&text), expected);
+
+    // a keyword that is long, and overlaps with the other ones
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keyword("a ".repeat(500));
+    kp.add_keyword("a");
+    let text = "a ".repeat(50_000);
+    let expected: Vec<_> = kp.extract_keywords_with_span(&text).collect();
+    assert_eq!(kp.extract_keywords_par(&text), expected);
+
+    assert!(kp.extract_keywords_par("").is_empty());
+}
+
+
+#[test]
+fn test_extract_keywords_all() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["York", "New York", "New York City", "City"]);
+    kp.add_keywords_from_iter(["big apple", "apple pie"]);
+
+    let text = "New York City";
+    assert_eq!(kp.extract_keywords_with_span(text).collect::<Vec<_>>(), [("New York City", 0, 13)]);
+    assert_eq!(
+        kp.extract_keywords_all(text).collect::<Vec<_>>(),
+        [("New York", 0, 8), ("New York City", 0, 13), ("York", 4, 8), ("City", 9, 13)]
+    );
+
+    assert_eq!(
+        kp.extract_keywords_all("a big apple pie").collect::<Vec<_>>(),
+        [("big apple", 2, 11), ("apple pie", 6, 15)]
+    );
+    assert_eq!(
+        kp.extract_keywords_all("New York").collect::<Vec<_>>(),
+        [("New York", 0, 8), ("York", 4, 8)]
+    );
+    assert_eq!(kp.extract_keywords_all("").count(), 0);
+    assert_eq!(kp.extract_keywords_all("Boston").count(), 0);
+
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["machine", "machine learning"]);
+    assert_eq!(
+        kp.extract_keywords_all("MACHINE Learning").collect::<Vec<_>>(),
+        [("machine", 0, 7), ("machine learning", 0, 16)]
+    );
+}
+
+
+#[test]
+fn test_extract_keywords_from_reader() {
+    use std::io::BufReader;
+
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New York", "New York City", "can't", "café", "Maße"]);
+    kp.add_keyword("a b c d e");
+    kp.add_keyword_with_clean_word("🦀", "crab");
+
+    let text = "I CAN'T believe it, new york city has a café.\nNew\nYork, MASSE, 🦀🦀, \
+        a b c d, a b c d e, can, can'";
+    let expected: Vec<_> = kp.extract_keywords_with_span(text).collect();
+    assert_eq!(expected.len(), 7);
+
+    // keywords and chars are split between the reads
+    for capacity in 1..=20 {
+        let reader = BufReader::with_capacity(capacity, text.as_bytes());
+        let found: Vec<_> = kp
+            .extract_keywords_from_reader(reader)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(found, expected, "capacity: {capacity}");
+    }
+
+    assert_eq!(kp.extract_keywords_from_reader("".as_bytes()).count(), 0);
+    assert_eq!(kp.extract_keywords_from_reader("café".as_bytes()).count(), 1);
+
+    let invalid_utf8 = [b"cafe ".as_slice(), &[0xff, 0xfe], b" cafe"].concat();
+    let mut iter = kp.extract_keywords_from_reader(invalid_utf8.as_slice());
+    assert_eq!(iter.next().unwrap().unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    assert!(iter.next().is_none());
+
+    // a truncated char at the end of the stream
+    let mut iter = kp.extract_keywords_from_reader(&"café".as_bytes()[..4]);
+    assert!(iter.next().unwrap().is_err());
+}
+
+
+#[test]
+fn test_extract_keywords_from_reader_with_value() {
+    use std::io::BufReader;
+
+    let mut kp = case_insensitive::KeywordProcessor::<u32>::default();
+    kp.add_keyword_with_value("New York", 1);
+    kp.add_keyword_with_value("Paris", 2);
+
+    let text = "paris, NEW YORK and new york";
+    for capacity in 1..=8 {
+        let reader = BufReader::with_capacity(capacity, text.as_bytes());
+        let found: Vec<_> = kp
+            .extract_keywords_from_reader_with_value(reader)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(found, [(&2, 0, 5), (&1, 7, 15), (&1, 20, 28)], "capacity: {capacity}");
+    }
+}
+
+
+#[test]
+fn test_scanner() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New York", "New York City", "can't", "café", "a b c d e"]);
+    kp.add_keyword_with_clean_word("🦀", "crab");
+
+    let text = "I CAN'T believe it, new york city has a café.\nNew\nYork, 🦀🦀, a b c d, \
+        a b c d e, can, can'";
+    let expected: Vec<_> = kp.extract_keywords_with_span(text).collect();
+    assert_eq!(expected.len(), 6);
+
+    // the keywords are split between the chunks
+    for chunk_len in 1..=20 {
+        let mut scanner = kp.scanner();
+        let mut found = Vec::new();
+        let mut chunk_start = 0;
+        while chunk_start < text.len() {
+            let mut chunk_end = (chunk_start + chunk_len).min(text.len());
+            while !text.is_char_boundary(chunk_end) {
+                chunk_end += 1;
+            }
+            found.extend(scanner.push(&text[chunk_start..chunk_end]));
+            chunk_start = chunk_end;
+        }
+        found.extend(scanner.finish());
+        assert_eq!(found, expected, "chunk length: {chunk_len}");
+    }
+
+    // a keyword is only returned once it can't get any longer
+    let mut scanner = kp.scanner();
+    assert_eq!(scanner.push("new york ").count(), 0);
+    assert_eq!(scanner.push("and more text after it").collect::<Vec<_>>(), [("New York", 0, 8)]);
+    assert_eq!(scanner.finish().count(), 0);
+
+    let mut scanner = kp.scanner();
+    assert_eq!(scanner.push("").count(), 0);
+    assert_eq!(scanner.finish_with_value().collect::<Vec<_>>(), []);
+}
+
+
+#[test]
+fn test_scanner_tokenizes_each_chunk_once() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use flashtext2::Tokenizer;
+
+    /// Splits the text into its words and spaces, and counts the bytes that were tokenized.
+    struct CountingTokenizer(Rc<Cell<usize>>);
+
+    impl Tokenizer for CountingTokenizer {
+        fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+            self.0.set(self.0.get() + text.len());
+            flashtext2::SimpleTokenizer.tokenize(text)
+        }
+    }
+
+    let count = Rc::new(Cell::new(0));
+    let mut kp = case_sensitive::KeywordProcessor::with_tokenizer(CountingTokenizer(count.clone()));
+    kp.add_keyword("x ".repeat(50));
+    let text = "x y ".repeat(10_000);
+
+    count.set(0);
+    let mut scanner = kp.scanner();
+    let mut found = 0;
+    for chunk in text.as_bytes().chunks(3) {
+        found += scanner.push(std::str::from_utf8(chunk).unwrap()).count();
+    }
+    found += scanner.finish().count();
+    assert_eq!(found, 0);
+    // besides the last tokens before each chunk, which may still change
+    assert!(count.get() < 3 * text.len(), "{} bytes", count.get());
+}
+
+
+#[test]
+fn test_extract_keywords_is_lazy() {
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use flashtext2::Tokenizer;
+
+    /// Splits on the spaces, and counts the tokens that were pulled.
+    #[derive(Default)]
+    struct CountingTokenizer(Rc<Cell<usize>>);
+
+    impl Tokenizer for CountingTokenizer {
+        fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+            let count = self.0.clone();
+            text.split(' ')
+                .filter(|token| !token.is_empty())
+                .map(move |token| (token.as_ptr() as usize - text.as_ptr() as usize, token))
+                .inspect(move |_| count.set(count.get() + 1))
+        }
+    }
+
+    let count = Rc::new(Cell::new(0));
+    let mut kp = case_sensitive::KeywordProcessor::with_tokenizer(CountingTokenizer(count.clone()));
+    kp.add_keywords_from_iter(["New York", "New York City", "x x x"]);
+    let text = "I love New York City ".to_string() + &"x ".repeat(10_000);
+
+    // only the tokens that a traversal can reach are pulled before the first keyword
+    count.set(0);
+    let mut keywords = kp.extract_keywords_with_span(&text);
+    assert_eq!(keywords.next().unwrap(), ("New York City", 7, 20));
+    assert!(count.get() < 10, "{} tokens", count.get());
+    assert_eq!(keywords.count(), 3333);
+
+    kp.add_keyword_with_priority("York", "York".to_string(), 1);
+    count.set(0);
+    let mut keywords = kp.extract_keywords_with_span(&text);
+    assert_eq!(keywords.next().unwrap(), ("York", 11, 15));
+    assert!(count.get() < 20, "{} tokens", count.get());
+}
+
+
+#[test]
+fn test_custom_tokenizer() {
+    use flashtext2::Tokenizer;
+
+    /// Splits on whitespace, `_` and `.`, and drops the separators.
+    #[derive(Default)]
+    struct CodeTokenizer;
+
+    impl Tokenizer for CodeTokenizer {
+        fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+            text.split(|c: char| c.is_whitespace() || c == '_' || c == '.')
+                .filter(|token| !token.is_empty())
+                .map(move |token| (token.as_ptr() as usize - text.as_ptr() as usize, token))
+        }
+    }
+
+    let mut kp = case_sensitive::KeywordProcessor::with_tokenizer(CodeTokenizer);
+    kp.add_keyword("foo bar");
+    kp.add_keyword_with_clean_word("std.io", "std::io");
+
+    assert!(kp.contains("foo   bar"));
+    assert!(kp.contains("foo_bar"));
+    assert_eq!(
+        kp.extract_keywords_with_span("foo bar foo   bar\tfoo_bar std_io").collect::<Vec<_>>(),
+        [("foo bar", 0, 7), ("foo bar", 8, 17), ("foo bar", 18, 25), ("std::io", 26, 32)]
+    );
+    // the punctuation is part of the token, unlike with the default tokenizer
+    assert_eq!(kp.extract_keywords("foo bar,").count(), 0);
+
+    let mut kp = case_insensitive::KeywordProcessor::with_tokenizer(CodeTokenizer);
+    kp.add_keyword("Foo Bar");
+    assert_eq!(kp.extract_keywords("FOO___BAR").collect::<Vec<_>>(), ["Foo Bar"]);
+    assert!(kp.remove_keyword("foo.bar"));
+    assert!(kp.is_empty());
+
+    // a borrowed tokenizer can be shared between processors
+    let tokenizer = CodeTokenizer;
+    let mut processors = [
+        KeywordProcessor::with_tokenizer(&tokenizer),
+        KeywordProcessor::with_tokenizer(&tokenizer),
+    ];
+    processors[0].add_keyword("foo bar");
+    processors[1].add_keyword("std io");
+    assert_eq!(processors[0].extract_keywords("foo_bar").collect::<Vec<_>>(), ["foo bar"]);
+    assert_eq!(processors[1].extract_keywords("std.io").collect::<Vec<_>>(), ["std io"]);
+}
+
+
+#[test]
+fn test_case_folding() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    // "ß" folds to "ss", whether the other side is ASCII or not
+    kp.add_keyword("Maße");
+    kp.add_keyword("STRASSE");
+    assert!(kp.contains("MASSE"));
+    assert!(kp.contains("masse"));
+    assert!(kp.contains("MAẞE"));
+    assert!(kp.contains("Straße"));
+    assert!(kp.contains("strasse"));
+    assert_eq!(kp.extract_keywords("masse, straße").collect::<Vec<_>>(), ["Maße", "STRASSE"]);
+
+    // the Greek sigma and final sigma are the same letter
+    kp.add_keyword("ΟΔΟΣ");
+    assert!(kp.contains("οδος"));
+    assert!(kp.contains("οδοσ"));
+    assert!(kp.contains("Οδος"));
+    assert!(!kp.contains("οδός"));
+
+    // the Turkish dotted capital I folds to "i̇" ("i" + U+0307), and the dotless "ı" is its own
+    // letter, since the folding isn't locale-aware.
+    kp.add_keyword("İstanbul");
+    assert!(kp.contains("İSTANBUL"));
+    assert!(kp.contains("i\u{307}stanbul"));
+    assert!(!kp.contains("istanbul"));
+    kp.add_keyword("ılık");
+    assert!(kp.contains("ılık"));
+    assert!(!kp.contains("ILIK"));
+    kp.add_keyword("Iowa");
+    assert!(kp.contains("iowa"));
+    assert!(!kp.contains("ıowa"));
+
+    // the keywords that are added with a different case are the same keyword
+    let len = kp.len();
+    kp.add_keyword("MASSE");
+    kp.add_keyword("οδοσ");
+    assert_eq!(kp.len(), len);
+    assert_eq!(kp.get_clean_word("MASSE"), Some("MASSE"));
+}
+
+
+#[test]
+fn test_extend() {
+    let mut kp: case_sensitive::KeywordProcessor = ["py", "rs"].into_iter().collect();
+    assert_eq!(kp.len(), 2);
+
+    // re-adding a keyword doesn't increase the length, but does overwrite its clean word
+    kp.extend([("py", "Python"), ("c", "C")]);
+    assert_eq!(kp.len(), 3);
+    assert_eq!(kp.get_clean_word("py"), Some("Python"));
+
+    let words = vec![String::from("rs"), String::from("go")];
+    kp.extend(&words);
+    assert_eq!(kp.len(), 4);
+    assert_eq!(kp.get_clean_word("rs"), Some("rs"));
+
+    let mut kp2 = case_sensitive::KeywordProcessor::with_capacity(100);
+    kp2.add_keywords_with_clean_word_from_iter([("py", "Python"), ("c", "C"), ("rs", "rs")]);
+    kp2.add_keyword("go");
+    // the capacity isn't part of the equality
+    assert_eq!(kp, kp2);
+
+    let kp: case_insensitive::KeywordProcessor = [("Py", "Python"), ("PY", "Python 3")]
+        .into_iter()
+        .collect();
+    assert_eq!(kp.len(), 1);
+    assert_eq!(kp.extract_keywords("py").collect::<Vec<_>>(), ["Python 3"]);
+
+    // owned strings are moved into the processor
+    let mut kp: case_sensitive::KeywordProcessor =
+        vec![String::from("py"), String::from("rs")].into_iter().collect();
+    kp.extend([(String::from("go"), String::from("Go"))]);
+    kp.extend(vec![String::from("c")]);
+    assert_eq!(kp.len(), 4);
+    assert_eq!(kp.get_clean_word("go"), Some("Go"));
+
+    let kp: case_sensitive::KeywordProcessor = ["py", "rs"]
+        .iter()
+        .map(|w| (w.to_string(), w.to_uppercase()))
+        .collect();
+    assert_eq!(kp.extract_keywords("py and rs").collect::<Vec<_>>(), ["PY", "RS"]);
+}
+
+
+#[test]
+fn test_replace_keywords_with() {
+    use std::borrow::Cow;
+
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keyword_with_clean_word("John Smith", "<name>");
+    kp.add_keyword_with_clean_word("555-1234", "<phone>");
+    let text = "Call john smith at 555-1234, or JOHN SMITH.";
+
+    assert_eq!(kp.replace_keywords(text), "Call <name> at <phone>, or <name>.");
+    assert_eq!(
+        kp.replace_keywords_with(text, |m| format!("[REDACTED:{}]", m.range().len()).into()),
+        "Call [REDACTED:10] at [REDACTED:8], or [REDACTED:10]."
+    );
+    assert_eq!(
+        kp.replace_keywords_with(text, |m| m.text(text).to_uppercase().into()),
+        "Call JOHN SMITH at 555-1234, or JOHN SMITH."
+    );
+
+    // deleting the keywords
+    assert_eq!(kp.replace_keywords_with(text, |_| Cow::Borrowed("")), "Call  at , or .");
+    assert_eq!(kp.replace_keywords_with("john smith", |_| "".into()), "");
+    // the matched text itself can be borrowed
+    assert_eq!(kp.replace_keywords_with(text, |m| Cow::Borrowed(m.text(text))), text);
+
+    let mut spans = Vec::new();
+    kp.replace_keywords_with(text, |m| {
+        spans.push((m.clean_word.to_string(), m.start, m.end));
+        Cow::Borrowed(m.clean_word)
+    });
+    assert_eq!(spans.len(), 3);
+    assert_eq!(spans[1], ("<phone>".to_string(), 19, 27));
+}
+
+
+#[test]
+fn test_extract_keywords_with_char_span() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["café", "cafe\u{301}", "🦀", "naïve coder", "👍🏽"]);
+
+    let text = "🦀 I'm a naïve coder at the café (or cafe\u{301}) 👍🏽!";
+    let byte_spans: Vec<_> = kp.extract_keywords_with_span(text).collect();
+    let char_spans: Vec<_> = kp.extract_keywords_with_char_span(text).collect();
+    assert_eq!(byte_spans.len(), 5);
+    assert_eq!(char_spans.len(), byte_spans.len());
+    assert_eq!(char_spans[0], ("🦀", 0, 1));
+    assert_eq!(char_spans[1], ("naïve coder", 8, 19));
+
+    let chars: Vec<char> = text.chars().collect();
+    for (byte_span, char_span) in byte_spans.into_iter().zip(char_spans) {
+        let (char_start, char_end) = (char_span.start, char_span.end);
+        assert_eq!(byte_span.clean_word, char_span.clean_word);
+        assert_eq!(byte_span.keyword, char_span.keyword);
+        assert_eq!(text.chars().nth(char_start), text[byte_span.start..].chars().next());
+        assert_eq!(chars[char_start..char_end].iter().collect::<String>(), byte_span.text(text));
+    }
+
+    assert_eq!(kp.extract_keywords_with_char_span("").count(), 0);
+}
+
+
+#[test]
+fn test_node_count_and_shrink_to_fit() {
+    let mut kp = case_sensitive::KeywordProcessor::with_capacity(1000);
+    assert_eq!(kp.node_count(), 0);
+
+    kp.add_keyword("New York"); // ["New", " ", "York"]
+    assert_eq!(kp.node_count(), 3);
+    kp.add_keyword("New York City"); // + [" ", "City"]
+    assert_eq!(kp.node_count(), 5);
+    kp.add_keyword("New"); // already a node
+    assert_eq!(kp.node_count(), 5);
+    kp.add_keyword("Boston");
+    assert_eq!(kp.node_count(), 6);
+
+    // re-adding a keyword doesn't add any node
+    kp.add_keyword("New York City");
+    assert_eq!(kp.node_count(), 6);
+    assert_eq!(kp.len(), 4);
+
+    let before = kp.keywords_with_clean_word().count();
+    kp.shrink_to_fit();
+    assert_eq!(kp.node_count(), 6);
+    assert_eq!(kp.keywords_with_clean_word().count(), before);
+    assert_eq!(kp.extract_keywords("New York City").collect::<Vec<_>>(), ["New York City"]);
+
+    assert!(kp.remove_keyword("New York City"));
+    assert_eq!(kp.node_count(), 4);
+
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["Foo Bar", "foo bar", "FOO"]);
+    assert_eq!(kp.node_count(), 3);
+    kp.shrink_to_fit();
+    assert_eq!(kp.extract_keywords("FOO BAR").collect::<Vec<_>>(), ["foo bar"]);
+}
+
+
+#[test]
+fn test_remove_keyword_prunes_nodes() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["a b c", "a b d", "a", "x y z"]);
+    assert_eq!(kp.node_count(), 11);
+
+    // only the "d" node can be pruned, since "a b" still leads to "a b c"
+    assert!(kp.remove_keyword("a b d"));
+    assert_eq!(kp.node_count(), 10);
+
+    // "a" has no more children once "a b c" is removed, but is still a keyword
+    assert!(kp.remove_keyword("a b c"));
+    assert_eq!(kp.node_count(), 6);
+    assert!(kp.remove_keyword("a"));
+    assert_eq!(kp.node_count(), 5);
+
+    // a prefix that isn't a keyword can't be removed, and nothing is pruned
+    assert!(!kp.remove_keyword("x y"));
+    assert_eq!(kp.node_count(), 5);
+    assert!(kp.remove_keyword("x y z"));
+    assert_eq!(kp.node_count(), 0);
+    assert!(kp.is_empty());
+}
+
+
+#[test]
+fn test_clear() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["Hello", "Hello World", "Foo"]);
+    kp.clear();
+    assert!(kp.is_empty());
+    assert_eq!(kp.node_count(), 0);
+    assert_eq!(kp.keywords().count(), 0);
+    assert_eq!(kp.extract_keywords("hello world").count(), 0);
+    assert_eq!(kp, case_insensitive::KeywordProcessor::new());
+
+    // the processor can be reused
+    kp.add_keyword("Bar");
+    assert_eq!(kp.len(), 1);
+    assert_eq!(kp.extract_keywords("foo bar").collect::<Vec<_>>(), ["Bar"]);
+}
+
+
+#[test]
+fn test_merge() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keywords_with_clean_word_from_iter([("py", "Python"), ("New York", "NY")]);
+
+    let mut other = case_sensitive::KeywordProcessor::new();
+    other.add_keywords_with_clean_word_from_iter([
+        ("py", "Python 3"),
+        ("New York City", "NYC"),
+        ("rs", "Rust"),
+    ]);
+
+    kp.merge(&other);
+    assert_eq!(kp.len(), 4);
+    assert_eq!(other.len(), 3);
+    // the clean word of `other` wins
+    assert_eq!(kp.get_clean_word("py"), Some("Python 3"));
+    assert_eq!(kp.get_clean_word("New York"), Some("NY"));
+    assert_eq!(kp.get_clean_word("New York City"), Some("NYC"));
+    assert_eq!(kp.get_clean_word("rs"), Some("Rust"));
+
+    let mut expected = case_sensitive::KeywordProcessor::new();
+    expected.add_keywords_with_clean_word_from_iter([
+        ("New York", "NY"),
+        ("py", "Python 3"),
+        ("New York City", "NYC"),
+        ("rs", "Rust"),
+    ]);
+    assert_eq!(kp, expected);
+
+    // merging twice, or merging an empty processor, doesn't change anything
+    kp.merge(&other);
+    kp.merge(&case_sensitive::KeywordProcessor::new());
+    assert_eq!(kp, expected);
+
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keyword("Hello");
+    let mut other = case_insensitive::KeywordProcessor::new();
+    other.add_keywords_from_iter(["HELLO", "hello world"]);
+    kp.merge(&other);
+    assert_eq!(kp.len(), 2);
+    assert_eq!(
+        kp.extract_keywords("Hello World, hello").collect::<Vec<_>>(),
+        ["hello world", "HELLO"]
+    );
+}
+
+
+#[test]
+fn test_values() {
+    #[derive(Debug, Clone, PartialEq)]
+    enum Entity {
+        City(u32),
+        Person(u32),
+    }
+
+    let mut kp = case_insensitive::KeywordProcessor::<Entity>::default();
+    kp.add_keyword_with_value("New York", Entity::City(1));
+    kp.add_keyword_with_value("Big Apple", Entity::City(1));
+    kp.add_keyword_with_value("Ada Lovelace", Entity::Person(7));
+    assert_eq!(kp.len(), 3);
+    assert_eq!(kp.get_value("new york"), Some(&Entity::City(1)));
+    assert!(kp.contains("ADA LOVELACE"));
+    assert_eq!(kp.get_value("New"), None);
+
+    let text = "Ada lovelace never visited the big apple.";
+    assert_eq!(
+        kp.extract_keywords_with_value(text).collect::<Vec<_>>(),
+        [
+            ("Ada lovelace", &Entity::Person(7), 0, 12),
+            ("big apple", &Entity::City(1), 31, 40),
+        ]
+    );
+
+    let mut keywords: Vec<_> = kp.keywords_with_value().collect();
+    keywords.sort_by(|a, b| a.0.cmp(&b.0));
+    assert_eq!(
+        keywords,
+        [
+            (String::from("Ada Lovelace"), &Entity::Person(7)),
+            (String::from("Big Apple"), &Entity::City(1)),
+            (String::from("New York"), &Entity::City(1)),
+        ]
+    );
+
+    // overwriting the value of a keyword doesn't change the length
+    kp.add_keyword_with_value("new YORK", Entity::City(2));
+    assert_eq!(kp.len(), 3);
+    assert_eq!(kp.get_value("New York"), Some(&Entity::City(2)));
+
+    // a `String` processor is still a processor with values
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keyword_with_value("py", String::from("Python"));
+    assert_eq!(kp.get_clean_word("py"), Some("Python"));
+    assert_eq!(
+        kp.extract_keywords_with_value("py").collect::<Vec<_>>(),
+        [("py", &String::from("Python"), 0, 2)]
+    );
+}
+
+
+#[test]
+fn test_case_mode() {
+    let mut sensitive = KeywordProcessor::new();
+    let mut insensitive = KeywordProcessor::with_case_mode(CaseMode::Insensitive);
+    assert_eq!(sensitive.case_mode(), CaseMode::Sensitive);
+    assert_eq!(insensitive.case_mode(), CaseMode::Insensitive);
+    // an empty processor still isn't equal to one with another case mode
+    assert_ne!(sensitive, insensitive);
+
+    sensitive.add_keywords_from_iter(["Foo", "foo bar"]);
+    insensitive.add_keywords_from_iter(["Foo", "foo bar"]);
+    let text = "FOO BAR, foo, Foo";
+    assert_eq!(sensitive.extract_keywords(text).collect::<Vec<_>>(), ["Foo"]);
+    assert_eq!(insensitive.extract_keywords(text).collect::<Vec<_>>(), ["foo bar", "Foo", "Foo"]);
+
+    // the compatibility modules are the same processor, with the case mode already set
+    assert_eq!(case_sensitive::KeywordProcessor::new().case_mode(), CaseMode::Sensitive);
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["Foo", "foo bar"]);
+    assert_eq!(kp.case_mode(), CaseMode::Insensitive);
+    assert_eq!(kp.into_inner(), insensitive);
+
+    // the merged keywords follow the case mode of the processor they are merged into, and the
+    // tokens keep the casing they were first inserted with ("foo bar" shares the "Foo" node)
+    sensitive.merge(&insensitive);
+    assert_eq!(sensitive.len(), 3);
+    assert!(sensitive.contains("Foo bar"));
+    assert!(!sensitive.contains("FOO BAR"));
+    insensitive.merge(&sensitive);
+    assert_eq!(insensitive.len(), 2);
+}
+
+
+#[test]
+fn test_builder() {
+    #[derive(Debug, Default, Clone, PartialEq)]
+    struct WhitespaceTokenizer;
+
+    impl flashtext2::Tokenizer for WhitespaceTokenizer {
+        fn tokenize<'t>(&self, text: &'t str) -> impl Iterator<Item = (usize, &'t str)> + 't {
+            text.split_whitespace()
+                .map(move |token| (token.as_ptr() as usize - text.as_ptr() as usize, token))
+        }
+    }
+
+    // the defaults are the same as `new()`
+    assert_eq!(KeywordProcessor::builder().build(), KeywordProcessor::new());
+    assert_eq!(KeywordProcessorBuilder::new(), KeywordProcessor::builder());
+
+    let mut kp = KeywordProcessor::builder()
+        .case_mode(CaseMode::Insensitive)
+        .tokenizer(WhitespaceTokenizer)
+        .capacity(10)
+        .build();
+    kp.add_keyword_with_value("new york", 1);
+    assert_eq!(kp.case_mode(), CaseMode::Insensitive);
+    assert_eq!(kp.tokenizer(), &WhitespaceTokenizer);
+    assert_eq!(
+        kp.extract_keywords_with_value("NEW   YORK").collect::<Vec<_>>(),
+        [("NEW   YORK", &1, 0, 10)]
+    );
+
+    let kp = KeywordProcessor::builder().case_mode(CaseMode::Insensitive).build::<String>();
+    assert_eq!(kp, case_insensitive::KeywordProcessor::new().into_inner());
+}
+
+
+#[test]
+fn test_remove_keywords_from_iter() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New York", "New York City", "New Delhi", "Paris", "Rome"]);
+    let node_count = kp.node_count();
+
+    // the duplicates and the keywords that aren't there don't count
+    let removed = kp.remove_keywords_from_iter(["New York", "New York", "New", "Paris", "Tokyo"]);
+    assert_eq!(removed, 2);
+    assert_eq!(kp.len(), 3);
+    assert!(kp.contains("New York City"));
+    assert!(kp.contains("New Delhi"));
+    // "New York" is still a prefix of "New York City", so only "Paris" is pruned
+    assert_eq!(kp.node_count(), node_count - 1);
+
+    assert_eq!(kp.remove_keywords_from_iter(Vec::<String>::new()), 0);
+    let removed = kp.remove_keywords_from_iter(vec![
+        String::from("New York City"),
+        String::from("New Delhi"),
+        String::from("Rome"),
+    ]);
+    assert_eq!(removed, 3);
+    assert_eq!(kp, case_sensitive::KeywordProcessor::new());
+
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["Foo", "foo bar", "Baz"]);
+    assert_eq!(kp.remove_keywords_from_iter(["FOO", "foo", "FOO BAR"]), 2);
+    assert_eq!(kp.keywords().collect::<Vec<_>>(), ["Baz"]);
+    assert_eq!(kp.node_count(), 1);
+}
+
+
+#[test]
+fn test_clone() {
+    let mut base = case_insensitive::KeywordProcessor::new();
+    base.add_keywords_with_clean_word_from_iter([("py", "Python"), ("rs", "Rust")]);
+
+    // the clone is a deep copy, so each variant can change independently
+    let mut tenant = base.clone();
+    assert_eq!(tenant, base);
+    tenant.add_keyword("go");
+    tenant.add_keyword_with_clean_word("PY", "Python 3");
+    tenant.remove_keyword("rs");
+
+    assert_eq!(base.len(), 2);
+    assert_eq!(base.get_clean_word("py"), Some("Python"));
+    assert_eq!(base.extract_keywords("PY RS GO").collect::<Vec<_>>(), ["Python", "Rust"]);
+    assert_eq!(tenant.len(), 2);
+    assert_eq!(tenant.extract_keywords("PY RS GO").collect::<Vec<_>>(), ["Python 3", "go"]);
+
+    let mut kp = KeywordProcessor::<u32>::default();
+    kp.add_keyword_with_value("one", 1);
+    let kp2 = kp.clone();
+    assert_eq!(kp2.case_mode(), CaseMode::Sensitive);
+    assert_eq!(kp2.get_value("one"), Some(&1));
+}
+
+
+#[test]
+fn test_priority() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New York", "New", "York"]);
+    let text = "I live in New York City";
+    assert_eq!(kp.extract_keywords(text).collect::<Vec<_>>(), ["New York"]);
+
+    // a keyword that starts inside another one, with a higher priority, wins
+    kp.add_keyword_with_priority("York City", String::from("York City"), 1);
+    assert_eq!(kp.len(), 4);
+    assert_eq!(kp.extract_keywords(text).collect::<Vec<_>>(), ["New", "York City"]);
+    assert_eq!(kp.replace_keywords(text), text);
+    // but not if the priority is the same (re-adding a keyword resets its priority)
+    kp.add_keyword("York City");
+    assert_eq!(kp.extract_keywords(text).collect::<Vec<_>>(), ["New York"]);
+
+    // a shorter keyword with a higher priority wins over the longest one
+    let mut kp = case_insensitive::KeywordProcessor::<u32>::default();
+    kp.add_keyword_with_value("machine learning", 1);
+    kp.add_keyword_with_priority("machine", 2, 5);
+    kp.add_keyword_with_value("learning", 3);
+    let values: Vec<_> = kp
+        .extract_keywords_with_value("Machine Learning")
+        .map(|(_, &value, start, end)| (value, start, end))
+        .collect();
+    assert_eq!(values, [(2, 0, 7), (3, 8, 16)]);
+
+    // the chain is resolved from left to right: "b c" beats "a b", and then loses to "c d"
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keyword_with_priority("a b", String::from("ab"), 1);
+    kp.add_keyword_with_priority("b c", String::from("bc"), 2);
+    kp.add_keyword_with_priority("c d", String::from("cd"), 3);
+    kp.add_keyword("a");
+    assert_eq!(kp.extract_keywords("a b c d").collect::<Vec<_>>(), ["a", "cd"]);
+    assert_eq!(kp.extract_keywords_all("a b c d").count(), 4);
+    assert_eq!(kp.clone().extract_keywords("b c").collect::<Vec<_>>(), ["bc"]);
+}
+
+
+#[test]
+fn test_merge_keeps_priorities() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keyword("New York");
+    kp.add_keyword_with_priority("York City", String::from("York City"), 5);
+    let text = "New York City";
+    assert_eq!(kp.extract_keywords(text).collect::<Vec<_>>(), ["York City"]);
+
+    // the merged keyword goes through the node of "York City", which keeps its priority
+    let mut other = KeywordProcessor::new();
+    other.add_keyword("York City Hall");
+    kp.merge(&other);
+    assert_eq!(kp.extract_keywords(text).collect::<Vec<_>>(), ["York City"]);
+
+    // but a keyword of `other` brings its own priority
+    let mut other = KeywordProcessor::new();
+    other.add_keyword("York City");
+    kp.merge(&other);
+    assert_eq!(kp.extract_keywords(text).collect::<Vec<_>>(), ["New York"]);
+}
+
+
+#[test]
+fn test_memory_usage() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    assert_eq!(kp.memory_usage(), 0);
+
+    kp.add_keyword("New York");
+    let usage = kp.memory_usage();
+    // at least the tokens and the clean word are stored on the heap
+    assert!(usage >= "New York".len() * 2);
+
+    kp.add_keyword_with_clean_word("New York City", "NYC");
+    assert!(kp.memory_usage() > usage);
+    // the same trie, without counting the clean words
+    assert!(kp.memory_usage_with(|_| 0) < kp.memory_usage());
+
+    // the spare capacity is released
+    let mut kp2 = case_sensitive::KeywordProcessor::with_capacity(1_000);
+    kp2.add_keyword("New York");
+    kp2.add_keyword_with_clean_word("New York City", "NYC");
+    assert!(kp2.memory_usage() > kp.memory_usage());
+    kp.shrink_to_fit();
+    kp2.shrink_to_fit();
+    assert_eq!(kp2.memory_usage(), kp.memory_usage());
+
+    kp.clear();
+    kp.shrink_to_fit();
+    assert_eq!(kp.memory_usage(), 0);
+
+    let mut kp = KeywordProcessor::<Vec<u8>>::default();
+    kp.add_keyword_with_value("a", vec![0; 100]);
+    assert!(kp.memory_usage_with(|v| v.capacity()) >= 100 + "a".len());
+}
+
+
+#[test]
+fn test_max_keyword_tokens() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    assert_eq!(kp.max_keyword_tokens(), 0);
+
+    kp.add_keyword("py");
+    assert_eq!(kp.max_keyword_tokens(), 1);
+    // "New", " ", "York", " ", "City"
+    kp.add_keyword("New York City");
+    kp.add_keyword("New York");
+    assert_eq!(kp.max_keyword_tokens(), 5);
+
+    // re-adding a keyword doesn't count it twice
+    kp.add_keyword_with_clean_word("New York City", "NYC");
+    kp.remove_keyword("New York City");
+    assert_eq!(kp.max_keyword_tokens(), 3);
+    kp.remove_keyword("New York City");
+    assert_eq!(kp.max_keyword_tokens(), 3);
+
+    let mut other = case_sensitive::KeywordProcessor::new();
+    other.add_keyword("a b c d");
+    kp.merge(&other);
+    assert_eq!(kp.max_keyword_tokens(), 7);
+    assert_eq!(kp.remove_keywords_from_iter(["a b c d", "New York"]), 2);
+    assert_eq!(kp.max_keyword_tokens(), 1);
+
+    kp.clear();
+    assert_eq!(kp.max_keyword_tokens(), 0);
+
+    // the tokens are counted with the processor's tokenizer
+    let mut kp = case_insensitive::KeywordProcessor::<u32>::default();
+    kp.add_keyword_with_priority("Hello, World!", 1, 1);
+    assert_eq!(kp.max_keyword_tokens(), 5);
+}
+
+
+#[test]
+fn test_keywords_with_prefix() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New York", "New York City", "New Delhi", "Newark", "York"]);
+
+    let sorted = |keywords: &mut dyn ExactSizeIterator<Item = String>| {
+        assert_eq!(keywords.len(), keywords.size_hint().0);
+        let mut keywords: Vec<_> = keywords.collect();
+        keywords.sort();
+        keywords
+    };
+    assert_eq!(
+        sorted(&mut kp.keywords_with_prefix("new")),
+        ["New Delhi", "New York", "New York City"]
+    );
+    // the prefix itself is included, and the keywords keep the casing they were added with
+    assert_eq!(sorted(&mut kp.keywords_with_prefix("NEW YORK")), ["New York", "New York City"]);
+    assert_eq!(sorted(&mut kp.keywords_with_prefix("new york city")), ["New York City"]);
+    assert_eq!(sorted(&mut kp.keywords_with_prefix("")).len(), kp.len());
+    // only whole tokens are matched
+    assert_eq!(kp.keywords_with_prefix("New Yo").len(), 0);
+    assert_eq!(kp.keywords_with_prefix("Boston").len(), 0);
+}
+
+
+#[test]
+fn test_interner() {
+    use flashtext2::{Interner, Symbol};
+
+    let mut interner = Interner::new();
+    assert!(interner.is_empty());
+    let mut kp = KeywordProcessor::<Symbol>::default();
+    for (word, category) in [("py", "language"), ("rs", "language"), ("linux", "os")] {
+        kp.add_keyword_with_value(word, interner.intern(category));
+    }
+    assert_eq!(interner.len(), 2);
+    assert_eq!(kp.get_value("py"), kp.get_value("rs"));
+    assert_eq!(kp.get_value("py"), interner.get("language").as_ref());
+    assert_eq!(interner.get("browser"), None);
+
+    let language = interner.intern("language");
+    assert_eq!(language.index(), 0);
+    assert_eq!(interner.resolve(language), "language");
+    assert_eq!(interner.len(), 2);
+
+    let categories: Vec<_> = kp
+        .extract_keywords_with_value("rs and py on linux")
+        .map(|(keyword, &symbol, _, _)| (keyword, interner.resolve(symbol)))
+        .collect();
+    assert_eq!(categories, [("rs", "language"), ("py", "language"), ("linux", "os")]);
+}
+
+
+#[test]
+fn test_reserve() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.reserve(100);
+    assert!(kp.memory_usage() > 0);
+    assert!(kp.is_empty());
+    assert_eq!(kp.node_count(), 0);
+
+    // "New", " "
+    kp.reserve_prefix("New ", 100);
+    assert_eq!(kp.node_count(), 2);
+    assert!(kp.is_empty());
+    assert!(!kp.contains("New "));
+    assert_eq!(kp.keywords().count(), 0);
+
+    kp.add_keywords_from_iter(["New York", "New Delhi"]);
+    assert_eq!(kp.node_count(), 4);
+    assert_eq!(kp.extract_keywords("New Delhi, New York").count(), 2);
+    // the reserved nodes are pruned with the last keyword below them
+    kp.remove_keywords_from_iter(["New York", "New Delhi"]);
+    assert_eq!(kp.node_count(), 0);
+}
+
+
+#[test]
+fn test_compact() {
+    let mut kp = case_sensitive::KeywordProcessor::with_capacity(1_000);
+    kp.add_keywords_from_iter(["New York", "New York City", "Paris"]);
+    kp.reserve_prefix("New Delhi", 100);
+    kp.reserve_prefix("Rome", 100);
+    let (node_count, memory_usage) = (kp.node_count(), kp.memory_usage());
+
+    let kp2 = kp.clone();
+    kp.compact();
+    // "Delhi" (after "New ") and "Rome" are gone
+    assert_eq!(kp.node_count(), node_count - 2);
+    assert!(kp.memory_usage() < memory_usage);
+    assert_eq!(kp.len(), kp2.len());
+    let text = "New York City, Paris, New York";
+    assert_eq!(
+        kp.extract_keywords(text).collect::<Vec<_>>(),
+        kp2.extract_keywords(text).collect::<Vec<_>>()
+    );
+
+    // compacting twice doesn't change anything
+    let kp2 = kp.clone();
+    kp.compact();
+    assert_eq!(kp, kp2);
+    assert_eq!(kp.memory_usage(), kp2.memory_usage());
+}
+
+
+#[test]
+fn test_first_token_filter() {
+    // the tokens that can't start a keyword are skipped before the lookup, but a char can
+    // still match another one that folds into the same letters
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["ſtop", "\u{212A}elvin", "ßig", "Émile", "xyz"]);
+    assert_eq!(
+        kp.extract_keywords("STOP kelvin SSIG émile XYZ").collect::<Vec<_>>(),
+        ["ſtop", "\u{212A}elvin", "ßig", "Émile", "xyz"]
+    );
+
+    // the filter follows the removals and the pruning
+    assert!(kp.remove_keyword("xyz"));
+    assert_eq!(kp.extract_keywords("xyz").count(), 0);
+    kp.add_keyword("XY");
+    assert_eq!(kp.extract_keywords("xy").collect::<Vec<_>>(), ["XY"]);
+    kp.reserve_prefix("Zed", 10);
+    kp.compact();
+    kp.add_keyword("zoo");
+    assert_eq!(kp.extract_keywords("ZOO xy").collect::<Vec<_>>(), ["zoo", "XY"]);
+    kp.clear();
+    kp.add_keyword("a");
+    assert_eq!(kp.extract_keywords("A xy").collect::<Vec<_>>(), ["a"]);
+
+    let mut kp = KeywordProcessor::with_case_mode(CaseMode::AsciiInsensitive);
+    kp.add_keywords_from_iter(["Rust", "été"]);
+    assert_eq!(kp.extract_keywords("rust ÉTÉ été").collect::<Vec<_>>(), ["Rust", "été"]);
+
+    // with enough first tokens, they are also filtered by their hash
+    let words = ["ſtop", "\u{212A}elvin", "ßig", "Émile", "Straße", "ﬂour", "Rust", "été"];
+    for case_mode in [CaseMode::Sensitive, CaseMode::Insensitive, CaseMode::AsciiInsensitive] {
+        let mut kp = KeywordProcessor::with_case_mode(case_mode);
+        kp.add_keywords_from_iter((0..1_000).map(|i| format!("w{i}")));
+        kp.add_keywords_from_iter(words);
+        for word in words.into_iter().chain(["w0", "w999"]) {
+            assert_eq!(kp.extract_keywords(word).collect::<Vec<_>>(), [word], "{case_mode:?}");
+        }
+        assert_eq!(kp.extract_keywords("w1000 ww1 Ruste").count(), 0);
+        if case_mode != CaseMode::Sensitive {
+            assert_eq!(kp.extract_keywords("W7 rUST été").count(), 3, "{case_mode:?}");
+        }
+        if case_mode == CaseMode::Insensitive {
+            let text = "STOP kelvin SSIG STRASSE flour";
+            assert_eq!(kp.extract_keywords(text).count(), 5);
+        }
+
+        // the filter is rebuilt without the removed tokens
+        assert_eq!(kp.remove_keywords_from_iter((0..1_000).map(|i| format!("w{i}"))), 1_000);
+        kp.shrink_to_fit();
+        assert_eq!(kp.extract_keywords("w0 Rust").collect::<Vec<_>>(), ["Rust"]);
+    }
+}
+
+
+#[test]
+fn test_first_byte_filter_full_folding() {
+    // the only keywords start with a char that folds into several ASCII ones
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keyword("ẞ");
+    assert_eq!(kp.extract_keywords("ss SS ß").collect::<Vec<_>>(), ["ẞ", "ẞ", "ẞ"]);
+    kp.add_keyword("ﬀ");
+    assert_eq!(kp.extract_keywords("FF ff").collect::<Vec<_>>(), ["ﬀ", "ﬀ"]);
+    assert!(kp.remove_keyword("ẞ"));
+    assert_eq!(kp.extract_keywords("ss").count(), 0);
+}
+
+
+#[test]
+fn test_nodes_with_many_children() {
+    // the children of "New" go from a vector to a hashmap, and back once they are shrunk
+    for case_mode in [CaseMode::Sensitive, CaseMode::Insensitive, CaseMode::AsciiInsensitive] {
+        let mut kp = KeywordProcessor::with_case_mode(case_mode);
+        let cities = ["York", "Delhi", "Orleans", "Haven", "Jersey", "Mexico", "Zealand"];
+        for city in cities {
+            kp.add_keyword(format!("New {city}"));
+        }
+        for city in cities {
+            assert!(kp.contains(&format!("New {city}")), "{case_mode:?} {city}");
+        }
+        for city in &cities[2..] {
+            assert!(kp.remove_keyword(&format!("New {city}")));
+        }
+        kp.shrink_to_fit();
+        assert_eq!(kp.len(), 2);
+        assert_eq!(
+            kp.extract_keywords("new york, NEW DELHI, New Haven").collect::<Vec<_>>(),
+            match case_mode {
+                CaseMode::Sensitive => vec![],
+                _ => vec!["New York", "New Delhi"],
+            }
+        );
+        kp.add_keyword("New Haven");
+        assert!(kp.contains("New Haven"));
+    }
+}
+
+
+#[test]
+fn test_remove_keyword_reuses_nodes() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New York City", "Paris"]);
+    let memory_usage = kp.memory_usage();
+
+    assert!(kp.remove_keyword("New York City"));
+    assert_eq!(kp.node_count(), 1);
+    // the nodes of the removed keyword are reused by the next ones
+    kp.add_keywords_from_iter(["Los Angeles", "Rome"]);
+    assert_eq!(kp.node_count(), 5);
+    assert!(kp.memory_usage() <= memory_usage + "Los Angeles Rome".len());
+    assert_eq!(kp.extract_keywords("Rome, Paris, Los Angeles").count(), 3);
+
+    // two processors with the same keywords are equal, no matter where their nodes are
+    let mut kp2 = case_sensitive::KeywordProcessor::new();
+    kp2.add_keywords_from_iter(["Rome", "Los Angeles", "Paris"]);
+    assert_eq!(kp, kp2);
+
+    // once shrunk, the slots of the removed nodes are gone
+    assert!(kp.remove_keyword("Los Angeles"));
+    kp.shrink_to_fit();
+    let mut kp2 = case_sensitive::KeywordProcessor::new();
+    kp2.add_keywords_from_iter(["Rome", "Paris"]);
+    kp2.shrink_to_fit();
+    assert_eq!(kp.memory_usage(), kp2.memory_usage());
+    assert_eq!(kp, kp2);
+    kp.add_keyword("New York");
+    assert_eq!(kp.extract_keywords("Rome, Paris, New York").count(), 3);
+}
+
+
+#[test]
+fn test_add_sorted_keywords() {
+    let mut words = vec!["New York", "New York City", "New Delhi", "Rust", "rust", "New", "a b c"];
+    words.sort_unstable();
+    let kp = KeywordProcessor::from_sorted_keywords(&words);
+    let mut kp2 = KeywordProcessor::new();
+    kp2.add_keywords_from_iter(&words);
+    assert_eq!(kp, kp2);
+    assert_eq!(kp.len(), 7);
+
+    // in any order, with the same keyword twice (in another case)
+    for case_mode in [CaseMode::Sensitive, CaseMode::Insensitive, CaseMode::AsciiInsensitive] {
+        let words = ["New York City", "a b", "new york", "New Delhi", "NEW YORK", "a", "a b c"];
+        let mut kp = KeywordProcessor::builder().case_mode(case_mode).build();
+        kp.add_sorted_keywords_with_value(words.iter().zip(0..));
+        let mut kp2 = KeywordProcessor::builder().case_mode(case_mode).build();
+        for (i, word) in words.iter().enumerate() {
+            kp2.add_keyword_with_value(word, i);
+        }
+        assert_eq!(kp, kp2, "{case_mode:?}");
+        assert_eq!(kp.len(), kp2.len());
+        assert_eq!(kp.max_keyword_tokens(), 5);
+    }
+}
+
+
+#[test]
+fn test_from_sorted_keywords_unsorted() {
+    let words = ["Rust", "New York City", "new", "a b c", "New York", "a", "", "New Delhi", "a b"];
+    let kp = KeywordProcessor::from_sorted_keywords(words);
+    let mut kp2 = KeywordProcessor::new();
+    for word in words {
+        kp2.add_keyword(word);
+    }
+    assert_eq!(kp, kp2);
+    assert_eq!(kp.len(), kp2.len());
+    assert_eq!(kp.max_keyword_tokens(), kp2.max_keyword_tokens());
+    let mut keywords: Vec<_> = kp.keywords().collect();
+    let mut keywords2: Vec<_> = kp2.keywords().collect();
+    keywords.sort();
+    keywords2.sort();
+    assert_eq!(keywords, keywords2);
+
+    let text = "I love New York City and new rust, a b c";
+    assert_eq!(
+        kp.extract_keywords(text).collect::<Vec<_>>(),
+        kp2.extract_keywords(text).collect::<Vec<_>>()
+    );
+}
+
+
+#[test]
+fn test_tokens_are_shared() {
+    let mut kp = KeywordProcessor::<usize>::default();
+    // a long token that is in every keyword, but only stored once
+    let token = "x".repeat(10_000);
+    for i in 0..100 {
+        kp.add_keyword_with_value(&format!("{i} {token}"), i);
+        kp.add_keyword_with_value(&format!("{token} {i}"), i);
+    }
+    assert!(kp.memory_usage_with(|_| 0) < 10 * token.len());
+    assert_eq!(kp.extract_keywords_with_value(&format!("7 {token} 8")).count(), 1);
+
+    // the token is released once it isn't in any keyword anymore
+    let before = kp.memory_usage_with(|_| 0);
+    kp.remove_keywords_from_iter((0..100).map(|i| format!("{i} {token}")));
+    kp.remove_keywords_from_iter((0..100).map(|i| format!("{token} {i}")));
+    kp.shrink_to_fit();
+    assert!(kp.is_empty());
+    assert!(kp.memory_usage_with(|_| 0) < before - token.len());
+}
+
+
+#[test]
+fn test_extract_keywords_with_clean_word() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keyword_with_clean_word("NYC", "New York City");
+    kp.add_keyword_with_clean_word("big apple", "New York City");
+    kp.add_keyword("Rust");
+
+    let text = "nyc, aka the Big Apple, loves rust and RUST";
+    assert_eq!(
+        kp.extract_keywords_with_clean_word(text).collect::<Vec<_>>(),
+        [
+            ("nyc", "New York City"),
+            ("Big Apple", "New York City"),
+            ("rust", "Rust"),
+            ("RUST", "Rust"),
+        ]
+    );
+    assert_eq!(kp.extract_keywords_with_clean_word("").count(), 0);
+}
+
+
+#[test]
+fn test_extract_matches() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keyword_with_clean_word("NYC", "New York City");
+    kp.add_keyword("Rust");
+    let text = "I love rust and nyc";
+    assert_eq!(kp.extract_matches(text).collect::<Vec<_>>(), ["rust", "nyc"]);
+
+    // the matches are slices of the text itself
+    let (start, end) = (text.as_ptr() as usize, text.as_ptr() as usize + text.len());
+    assert!(kp.extract_matches(text).all(|m| {
+        let ptr = m.as_ptr() as usize;
+        start <= ptr && ptr + m.len() <= end
+    }));
+
+    let mut kp = KeywordProcessor::<u8>::default();
+    kp.add_keyword_with_value("a b", 0);
+    assert_eq!(kp.extract_matches("a b, a  b, a b").collect::<Vec<_>>(), ["a b", "a b"]);
+}
+
+
+#[test]
+fn test_extract_overlapping_keywords() {
+    let mut kp = case_sensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["big", "big apple", "apple pie", "pie"]);
+    let text = "big apple pie";
+
+    assert_eq!(kp.extract_keywords(text).collect::<Vec<_>>(), ["big apple", "pie"]);
+    assert_eq!(
+        kp.extract_overlapping_keywords(text).collect::<Vec<_>>(),
+        [("big apple", 0, 9), ("apple pie", 4, 13), ("pie", 10, 13)]
+    );
+    assert_eq!(kp.extract_keywords_all(text).count(), 4);
+    assert_eq!(kp.extract_overlapping_keywords("").count(), 0);
+    assert_eq!(kp.extract_overlapping_keywords("apple").count(), 0);
+}
+
+
+#[test]
+fn test_extract_keywords_nested() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["machine", "machine learning", "learning", "deep learning"]);
+    let text = "Machine learning, deep learning and learning";
+
+    assert_eq!(
+        kp.extract_keywords_nested(text).collect::<Vec<_>>(),
+        [
+            ("machine", 0, 7),
+            ("machine learning", 0, 16),
+            ("deep learning", 18, 31),
+            ("learning", 36, 44),
+        ]
+    );
+    // "learning" is also found inside both matches
+    assert_eq!(kp.extract_keywords_all(text).count(), 6);
+    assert_eq!(kp.extract_keywords_nested("").count(), 0);
+}
+
+
+#[test]
+fn test_extract_keywords_shortest() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New York", "New York City", "City Hall", "Hall"]);
+    let text = "New York City Hall";
+
+    assert_eq!(
+        kp.extract_keywords_shortest(text).collect::<Vec<_>>(),
+        [("New York", 0, 8), ("City Hall", 9, 18)]
+    );
+    assert_eq!(
+        kp.extract_keywords_with_span(text).collect::<Vec<_>>(),
+        [("New York City", 0, 13), ("Hall", 14, 18)]
+    );
+    assert_eq!(kp.extract_keywords_shortest("New Jersey").count(), 0);
+}
+
+
+#[test]
+fn test_extract_keyword_counts() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_with_clean_word_from_iter([
+        ("py", "Python"),
+        ("python", "Python"),
+        ("rust", "Rust"),
+    ]);
+
+    let counts = kp.extract_keyword_counts("rust, py and python, and rust again");
+    assert_eq!(counts.len(), 2);
+    assert_eq!(counts["Python"], 2);
+    assert_eq!(counts["Rust"], 2);
+    assert!(kp.extract_keyword_counts("nothing here").is_empty());
+}
+
+
+#[test]
+fn test_find_first() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter(["big apple", "apple pie", "pie", "a b c", "b"]);
+
+    for text in ["", "no keywords", "the big apple pie", "an apple pie", "pie", "a b", "a b d"] {
+        assert_eq!(kp.find_first(text), kp.extract_keywords_with_span(text).next(), "{text}");
+    }
+    assert_eq!(kp.find_first("the big apple pie").unwrap(), ("big apple", 4, 13));
+
+    kp.add_keyword_with_priority("apple pie", "apple pie".to_string(), 1);
+    assert_eq!(kp.find_first("the big apple pie").unwrap(), ("apple pie", 8, 17));
+}
+
+
+#[test]
+fn test_extract_keywords_with_limit() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter(["spam", "eggs"]);
+    let text = "spam eggs spam";
+
+    let (matches, truncated) = kp.extract_keywords_with_limit(text, 2);
+    assert_eq!(matches, [("spam", 0, 4), ("eggs", 5, 9)]);
+    assert!(truncated);
+
+    assert_eq!(kp.extract_keywords_with_limit(text, 3).0.len(), 3);
+    assert!(!kp.extract_keywords_with_limit(text, 3).1);
+    assert!(!kp.extract_keywords_with_limit(text, 10).1);
+    assert_eq!(kp.extract_keywords_with_limit(text, 0), (vec![], true));
+    assert_eq!(kp.extract_keywords_with_limit("", 0), (vec![], false));
+}
+
+
+#[test]
+fn test_extract_keywords_with_utf16_span() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter(["café", "🦀", "naïve coder"]);
+
+    let text = "🦀 I'm a naïve coder at the café 🦀";
+    let utf16_spans: Vec<_> = kp.extract_keywords_with_utf16_span(text).collect();
+    assert_eq!(
+        utf16_spans,
+        [("🦀", 0, 2), ("naïve coder", 9, 20), ("café", 28, 32), ("🦀", 33, 35)]
+    );
+
+    let units: Vec<u16> = text.encode_utf16().collect();
+    for m in utf16_spans {
+        assert_eq!(String::from_utf16(&units[m.range()]).unwrap(), m.keyword);
+    }
+}
+
+
+#[test]
+fn test_extract_keywords_with_token_span() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New York", "Boston", "!"]);
+
+    // ["I", " ", "love", " ", "New", " ", "York", " ", "and", " ", "Boston", "!"]
+    let text = "I love New York and Boston!";
+    assert_eq!(
+        kp.extract_keywords_with_token_span(text).collect::<Vec<_>>(),
+        [("New York", 4, 7), ("Boston", 10, 11), ("!", 11, 12)]
+    );
+    assert_eq!(kp.extract_keywords_with_token_span("").count(), 0);
+}
+
+
+#[test]
+fn test_extract_keywords_with_context() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New York", "Boston"]);
+
+    let text = "I love New York and Boston!";
+    let keywords: Vec<_> = kp.extract_keywords_with_context(text, 3).collect();
+    assert_eq!(keywords.len(), 2);
+    assert_eq!(keywords[0].matched, ("New York", 7, 15));
+    assert_eq!((keywords[0].left, keywords[0].right), (" love ", " and "));
+    assert_eq!(keywords[1].matched, ("Boston", 20, 26));
+    assert_eq!((keywords[1].left, keywords[1].right), (" and ", "!"));
+    let context = kp.extract_keywords_with_context(text, 0).next().unwrap();
+    assert_eq!((context.left, context.right), ("", ""));
+    let context = kp.extract_keywords_with_context(text, 100).next().unwrap();
+    assert_eq!((context.left, context.right), ("I love ", " and Boston!"));
+}
+
+
+#[test]
+fn test_match_keyword() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New York", "keyword"]);
+    let text = "new YORK and key-\nword";
+
+    let keywords = |matches: Vec<flashtext2::Match>| {
+        matches.into_iter().map(|m| m.keyword.into_owned()).collect::<Vec<_>>()
+    };
+    let spans: Vec<_> = kp.extract_keywords_with_span(text).collect();
+    assert_eq!(spans[0].keyword, spans[0].text(text));
+    assert_eq!(keywords(spans), ["new YORK"]);
+    assert_eq!(keywords(kp.extract_keywords_with_char_span(text).collect()), ["new YORK"]);
+    assert_eq!(keywords(kp.extract_keywords_dehyphenated(text)), ["new YORK", "key-\nword"]);
+
+    // the text isn't borrowed, so the matched part is copied
+    let (_, matches) = kp.extract_keywords_from_docs([text.to_string()]).next().unwrap();
+    assert_eq!(keywords(matches), ["new YORK"]);
+    let mut scanner = kp.scanner();
+    let mut matches: Vec<_> = scanner.push("new Yo").collect();
+    matches.extend(scanner.push("rk and KEYWORD"));
+    matches.extend(scanner.finish());
+    assert_eq!(keywords(matches), ["new York", "KEYWORD"]);
+    let matches = kp.extract_keywords_from_reader(text.as_bytes()).collect::<Result<_, _>>();
+    assert_eq!(keywords(matches.unwrap()), ["new YORK"]);
+}
+
+
+#[test]
+fn test_extract_sentences_with_keywords() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter(["Rust", "Python"]);
+
+    let text = "I write Rust. Nothing to see here! Rust or Python? Rust";
+    let sentences: Vec<_> = kp.extract_sentences_with_keywords(text).collect();
+    assert_eq!(sentences.len(), 3);
+    assert_eq!(sentences[0].0, "I write Rust. ");
+    assert_eq!(sentences[0].1, [("Rust", 8, 12)]);
+    assert_eq!(sentences[1].0, "Rust or Python? ");
+    assert_eq!(sentences[1].1, [("Rust", 35, 39), ("Python", 43, 49)]);
+    assert_eq!(sentences[2].0, "Rust");
+    assert_eq!(sentences[2].1, [("Rust", 51, 55)]);
+    for (sentence, keywords) in sentences {
+        for m in keywords {
+            assert_eq!(m.text(text), m.clean_word);
+            assert!(sentence.contains(m.clean_word));
+        }
+    }
+    assert_eq!(kp.extract_sentences_with_keywords("Nothing. At all.").count(), 0);
+}
+
+
+#[test]
+fn test_extract_keywords_fuzzy() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["receive", "machine learning", "a cat"]);
+
+    let text = "Did you recieve the Machin lerning book about a cat?";
+    assert_eq!(kp.extract_keywords(text).collect::<Vec<_>>(), ["a cat"]);
+    assert_eq!(
+        kp.extract_keywords_fuzzy(text, 2).collect::<Vec<_>>(),
+        [("receive", 8, 15), ("machine learning", 20, 34), ("a cat", 46, 51)]
+    );
+    assert_eq!(kp.extract_keywords_fuzzy(text, 0).count(), 1);
+    // a transposition is two edits
+    assert_eq!(kp.extract_keywords_fuzzy(text, 1).count(), 2);
+
+    // the short tokens must still match exactly
+    assert_eq!(kp.extract_keywords_fuzzy("a car", 2).count(), 1);
+    assert_eq!(kp.extract_keywords_fuzzy("o cat", 2).count(), 0);
+    assert_eq!(kp.extract_keywords_fuzzy("recv", 2).count(), 0);
+    assert_eq!(kp.extract_keywords_fuzzy("", 2).count(), 0);
+}
+
+
+#[test]
+fn test_suggest_and_fuzzy_case_folding() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keyword("Straße");
+
+    // the keys are compared with the full case folding, so "ß" is the same as "ss"
+    assert_eq!(kp.extract_keywords("STRASSE").collect::<Vec<_>>(), ["Straße"]);
+    assert_eq!(kp.suggest("STRASS").collect::<Vec<_>>(), ["Straße"]);
+    assert_eq!(kp.suggest("strasse").collect::<Vec<_>>(), ["Straße"]);
+    assert_eq!(kp.extract_keywords_fuzzy("STRASSEN", 1).count(), 1);
+    assert_eq!(kp.extract_keywords_fuzzy("STRASSEN", 0).count(), 0);
+}
+
+
+#[test]
+fn test_char_tokenizer() {
+    let mut kp = KeywordProcessor::builder()
+        .case_mode(CaseMode::Insensitive)
+        .tokenizer(flashtext2::CharTokenizer)
+        .build();
+    kp.add_keywords_with_clean_word_from_iter([
+        ("OH", "hydroxyl"),
+        ("CH3", "methyl"),
+        ("ß", "eszett"),
+    ]);
+
+    assert_eq!(
+        kp.extract_keywords_with_span("ch3ch2oh").collect::<Vec<_>>(),
+        [("methyl", 0, 3), ("hydroxyl", 6, 8)]
+    );
+    assert_eq!(kp.extract_keywords("Straße").collect::<Vec<_>>(), ["eszett"]);
+    assert_eq!(kp.keywords().count(), 3);
+
+    // the keywords don't need to be whole words
+    let mut kp = KeywordProcessor::with_tokenizer(flashtext2::CharTokenizer);
+    kp.add_keywords_from_iter(["#rust", "ID-42", "北京"]);
+    assert_eq!(
+        kp.extract_keywords_with_span("#rustlang,ID-421,我爱北京").collect::<Vec<_>>(),
+        [("#rust", 0, 5), ("ID-42", 10, 15), ("北京", 23, 29)]
+    );
+}
+
+
+#[test]
+fn test_grapheme_tokenizer() {
+    let mut kp = KeywordProcessor::builder().tokenizer(flashtext2::GraphemeTokenizer).build();
+    kp.add_keyword_with_clean_word("👍🏽", "thumbs up");
+    kp.add_keyword_with_clean_word("🇫🇷", "France");
+    kp.add_keyword_with_clean_word("e", "e");
+
+    // neither the skin tone nor the accent are split from their char
+    let text = "great👍🏽 🇩🇪🇫🇷 café e\u{301}";
+    assert_eq!(
+        kp.extract_keywords_with_span(text).collect::<Vec<_>>(),
+        [("e", 2, 3), ("thumbs up", 5, 13), ("France", 22, 30)]
+    );
+    // the char tokenizer splits them
+    let mut kp = KeywordProcessor::builder().tokenizer(flashtext2::CharTokenizer).build();
+    kp.add_keyword("👍");
+    assert_eq!(kp.extract_keywords("👍🏽").count(), 1);
+}
+
+
+#[test]
+fn test_separate_emoji() {
+    use flashtext2::{SeparateEmoji, WordBoundTokenizer};
+
+    let family = "👨\u{200d}👩\u{200d}👧\u{200d}👦";
+    let text = format!("ok{family}ok 👍🏽👍🏽 a🏽b a\u{200d}👩");
+
+    // the default tokenizer already keeps the sequences whole
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter([family, "👍🏽", "🏽", "👩"]);
+    assert_eq!(kp.extract_keywords(&text).collect::<Vec<_>>(), [family, "👍🏽", "👍🏽"]);
+
+    let mut kp = KeywordProcessor::with_tokenizer(SeparateEmoji(WordBoundTokenizer));
+    kp.add_keywords_from_iter([family, "👍🏽", "🏽", "👩", "ok"]);
+    assert_eq!(
+        kp.extract_keywords(&text).collect::<Vec<_>>(),
+        ["ok", family, "ok", "👍🏽", "👍🏽", "🏽", "👩"]
+    );
+}
+
+
+#[test]
+fn test_simple_tokenizer() {
+    use flashtext2::{SimpleTokenizer, Tokenizer};
+
+    let tokens: Vec<_> = SimpleTokenizer.tokenize("Hello,  wörld! 3.14").map(|(_, t)| t).collect();
+    assert_eq!(tokens, ["Hello", ",", "  ", "wörld", "!", " ", "3", ".", "14"]);
+
+    let mut kp = KeywordProcessor::builder()
+        .case_mode(CaseMode::Insensitive)
+        .tokenizer(SimpleTokenizer)
+        .build();
+    kp.add_keyword_with_clean_word("connection refused", "ECONNREFUSED");
+    kp.add_keyword("timeout");
+    let text = "[ERROR] Connection refused (timeout=30)";
+    assert_eq!(
+        kp.extract_keywords_with_span(text).collect::<Vec<_>>(),
+        [("ECONNREFUSED", 8, 26), ("timeout", 28, 35)]
+    );
+}
+
+
+#[test]
+fn test_non_word_boundaries() {
+    use flashtext2::NonWordBoundaries;
+
+    let mut tokenizer = NonWordBoundaries::new(['_']);
+    tokenizer.add_non_word_boundary('-');
+    tokenizer.add_non_word_boundary('_');
+    assert_eq!(tokenizer.non_word_boundaries(), ['_', '-']);
+
+    let mut kp = KeywordProcessor::builder()
+        .case_mode(CaseMode::Insensitive)
+        .tokenizer(tokenizer)
+        .build();
+    kp.add_keywords_from_iter(["covid", "COVID-19", "foo_bar", "foo"]);
+    assert_eq!(
+        kp.extract_keywords("Covid-19 and covid, foo_bar and foo-bar_baz").collect::<Vec<_>>(),
+        ["COVID-19", "covid", "foo_bar"]
+    );
+
+    let mut tokenizer = NonWordBoundaries::default();
+    tokenizer.set_non_word_boundaries(['.']);
+    let mut kp = KeywordProcessor::with_tokenizer(tokenizer);
+    kp.add_keyword("3.14");
+    assert_eq!(kp.extract_keywords("pi is 3.14, not 3.141").count(), 1);
+}
+
+
+#[test]
+fn test_word_chars() {
+    let mut kp = KeywordProcessor::builder()
+        .word_chars(|c: char| c.is_alphanumeric() || c == '#' || c == '@')
+        .build();
+    kp.add_keywords_from_iter(["#rust", "@rustlang", "rust"]);
+    assert_eq!(
+        kp.extract_keywords("rust, #rust, #rustacean and @rustlang!").collect::<Vec<_>>(),
+        ["rust", "#rust", "@rustlang"]
+    );
+
+    fn is_digit(c: char) -> bool {
+        c.is_ascii_digit()
+    }
+    let mut kp = KeywordProcessor::with_tokenizer(flashtext2::WordCharTokenizer(is_digit));
+    kp.add_keyword("42");
+    assert_eq!(kp.extract_keywords_with_span("x42y 420").collect::<Vec<_>>(), [("42", 1, 3)]);
+}
+
+
+#[test]
+fn test_cjk_chars() {
+    use flashtext2::{CjkChars, Tokenizer, WordBoundTokenizer};
+
+    let tokenizer = CjkChars(WordBoundTokenizer);
+    let tokens: Vec<_> = tokenizer.tokenize("東京タワー, iPhoneを").collect();
+    assert_eq!(tokens[..3], [(0, "東"), (3, "京"), (6, "タ")]);
+    assert_eq!(tokens[4..], [(12, "ー"), (15, ","), (16, " "), (17, "iPhone"), (23, "を")]);
+
+    let mut kp = KeywordProcessor::with_tokenizer(tokenizer);
+    kp.add_keywords_from_iter(["ワー", "コンピュータ", "iPhone", "北京"]);
+    let text = "東京タワーとコンピューターとiPhoneと北京";
+    assert_eq!(
+        kp.extract_keywords(text).collect::<Vec<_>>(),
+        ["ワー", "コンピュータ", "iPhone", "北京"]
+    );
+}
+
+
+#[test]
+fn test_normalization() {
+    use flashtext2::Normalization;
+
+    let decomposed = "cafe\u{301}";
+    let mut kp = KeywordProcessor::builder().normalization(Normalization::Nfc).build();
+    kp.add_keyword("café");
+    kp.add_keyword_with_clean_word("Cre\u{300}me", "crème");
+
+    let text = format!("un {decomposed}, une Crème");
+    assert_eq!(
+        kp.extract_keywords_with_span(&text).collect::<Vec<_>>(),
+        [("café", 3, 9), ("crème", 15, 21)]
+    );
+    assert!(kp.contains(decomposed));
+    // the keywords are stored normalized
+    assert!(kp.keywords().any(|keyword| keyword == "Crème"));
+    assert!(kp.remove_keyword("Crème"));
+
+    let mut kp = KeywordProcessor::builder()
+        .case_mode(CaseMode::Insensitive)
+        .normalization(Normalization::Nfkc)
+        .build();
+    kp.add_keyword("finance");
+    assert_eq!(kp.extract_keywords("ﬁnance and FINANCE").count(), 2);
+
+    // without normalization the encodings don't match
+    let mut kp = KeywordProcessor::new();
+    kp.add_keyword("café");
+    assert_eq!(kp.extract_keywords(&text).count(), 0);
+}
+
+
+#[test]
+fn test_fold_diacritics() {
+    let mut kp = KeywordProcessor::builder()
+        .case_mode(CaseMode::Insensitive)
+        .fold_diacritics()
+        .build();
+    kp.add_keyword_with_clean_word("cafe", "café");
+    kp.add_keyword("Señor");
+    kp.add_keyword("Łódź");
+
+    let text = "CAFÉ, cafe\u{301}, café, senor, Lodz, Łodz";
+    assert_eq!(
+        kp.extract_keywords(text).collect::<Vec<_>>(),
+        ["café", "café", "café", "Señor", "Łódź"]
+    );
+    assert!(kp.contains("Cafè"));
+}
+
+
+#[test]
+fn test_stem() {
+    fn stem(token: &str) -> String {
+        let token = token.strip_suffix('s').unwrap_or(token);
+        let token = token.strip_suffix("ning").or(token.strip_suffix("ing")).unwrap_or(token);
+        token.to_owned()
+    }
+
+    let mut kp = KeywordProcessor::builder()
+        .case_mode(CaseMode::Insensitive)
+        .stem(stem)
+        .build();
+    kp.add_keyword_with_clean_word("run", "RUN");
+    kp.add_keyword("walking dogs");
+    assert_eq!(
+        kp.extract_keywords_with_span("Running, runs and walks dogs").collect::<Vec<_>>(),
+        [("RUN", 0, 7), ("RUN", 9, 13), ("walking dogs", 18, 28)]
+    );
+    assert!(kp.contains("walk dog"));
+}
+
+
+#[test]
+fn test_locale_case() {
+    use flashtext2::Locale;
+
+    let mut kp = KeywordProcessor::builder()
+        .case_mode(CaseMode::Insensitive)
+        .locale(Locale::Turkish)
+        .build();
+    kp.add_keyword("ılık");
+    kp.add_keyword("İstanbul");
+    assert_eq!(
+        kp.extract_keywords("ILIK, ilik, istanbul, ISTANBUL, I\u{307}stanbul").collect::<Vec<_>>(),
+        ["ılık", "İstanbul", "İstanbul"]
+    );
+
+    // the default folding matches "I" with "i"
+    let mut kp = KeywordProcessor::with_case_mode(CaseMode::Insensitive);
+    kp.add_keyword("ılık");
+    assert_eq!(kp.extract_keywords("ILIK").count(), 0);
+
+    let mut kp = KeywordProcessor::builder()
+        .case_mode(CaseMode::Insensitive)
+        .locale(Locale::Lithuanian)
+        .build();
+    kp.add_keyword("i\u{307}\u{300}");
+    assert_eq!(kp.extract_keywords("Ì ì").count(), 2);
+}
+
+
+#[test]
+fn test_ascii() {
+    use flashtext2::{AsciiTokenizer, Tokenizer};
+
+    let tokens: Vec<_> = AsciiTokenizer.tokenize("GET /naïve?q=1  ok").map(|(_, t)| t).collect();
+    assert_eq!(tokens, ["GET", " ", "/", "naïve", "?", "q", "=", "1", "  ", "ok"]);
+
+    let mut kp = KeywordProcessor::builder()
+        .case_mode(CaseMode::Insensitive)
+        .ascii()
+        .build();
+    assert_eq!(kp.case_mode(), CaseMode::AsciiInsensitive);
+    kp.add_keyword("Connection reset");
+    kp.add_keyword("timeout");
+    let text = "ERROR: connection RESET by peer (TimeOut)";
+    assert_eq!(
+        kp.extract_keywords_with_span(text).collect::<Vec<_>>(),
+        [("Connection reset", 7, 23), ("timeout", 33, 40)]
+    );
+    assert!(kp.contains("TIMEOUT"));
+    assert_eq!(kp.suggest("connection RE").collect::<Vec<_>>(), ["Connection reset"]);
+
+    // only the ASCII letters are folded
+    kp.add_keyword("Straße");
+    assert_eq!(kp.extract_keywords("STRASSE straße STRAßE").collect::<Vec<_>>(), ["Straße"; 2]);
+}
+
+
+#[test]
+fn test_suggest() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New York", "New York City", "New Jersey", "Newark", "Boston"]);
+
+    let suggest = |partial_text| {
+        let mut keywords: Vec<_> = kp.suggest(partial_text).collect();
+        keywords.sort();
+        keywords
+    };
+    assert_eq!(suggest("new yo"), ["New York", "New York City"]);
+    assert_eq!(suggest("NEW"), ["New Jersey", "New York", "New York City", "Newark"]);
+    assert_eq!(suggest("new "), ["New Jersey", "New York", "New York City"]);
+    assert_eq!(suggest("New York City"), ["New York City"]);
+    assert_eq!(suggest("").len(), kp.len());
+    assert!(suggest("new x").is_empty());
+    assert!(suggest("old yo").is_empty());
+    assert_eq!(kp.suggest("new yo").len(), 2);
+}
+
+
+#[test]
+fn test_extract_keywords_skipping() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keywords_from_iter(["Bank America", "United States", "of"]);
+    let skippable = ["of", "the"];
+
+    let text = "the Bank of America of the United States";
+    assert_eq!(
+        kp.extract_keywords_skipping(text, &skippable).collect::<Vec<_>>(),
+        [("Bank America", 4, 19), ("of", 20, 22), ("United States", 27, 40)]
+    );
+    assert_eq!(kp.extract_keywords(text).collect::<Vec<_>>(), ["of", "of", "United States"]);
+    assert_eq!(
+        kp.extract_keywords_skipping("BANK OF THE AMERICA", &skippable).collect::<Vec<_>>(),
+        [("Bank America", 0, 19)]
+    );
+    // the skipped tokens are only consumed if the keyword is completed
+    assert_eq!(
+        kp.extract_keywords_skipping("bank of", &skippable).collect::<Vec<_>>(),
+        [("of", 5, 7)]
+    );
+    assert_eq!(kp.extract_keywords_skipping(text, &[] as &[&str]).count(), 3);
+}
+
+
+#[test]
+fn test_extract_keywords_dehyphenated() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter(["keyword", "New York City", "café"]);
+
+    let text = "A key-\nword in New\r\nYork\nCity, at the ca\u{AD}fé.";
+    assert_eq!(kp.extract_keywords(text).count(), 0);
+    let keywords = kp.extract_keywords_dehyphenated(text);
+    assert_eq!(keywords, [("keyword", 2, 11), ("New York City", 15, 29), ("café", 38, 45)]);
+    assert_eq!(&text[15..29], "New\r\nYork\nCity");
+
+    // a hyphen that isn't at the end of a line is kept
+    assert!(kp.extract_keywords_dehyphenated("key-word").is_empty());
+    assert!(kp.extract_keywords_dehyphenated("").is_empty());
+}
+
+
+#[test]
+fn test_ignore_punctuation() {
+    let mut kp = KeywordProcessor::builder()
+        .case_mode(CaseMode::Insensitive)
+        .tokenizer(flashtext2::IgnorePunctuation(flashtext2::WordBoundTokenizer))
+        .build();
+    kp.add_keywords_from_iter(["Department of Justice", "hello, world", "U.S. Navy", "🦀"]);
+
+    let text = "The Department, of Justice said: hello world (in the U.S Navy) 🦀!";
+    assert_eq!(
+        kp.extract_keywords_with_span(text).collect::<Vec<_>>(),
+        [
+            ("Department of Justice", 4, 26),
+            ("hello, world", 33, 44),
+            ("U.S. Navy", 53, 61),
+            ("🦀", 63, 67),
+        ]
+    );
+    // the punctuation isn't part of the reconstructed keywords
+    assert!(kp.keywords().any(|keyword| keyword == "hello world"));
+    assert_eq!(kp.extract_keywords("Department of - Justice").count(), 0);
+}
+
+
+#[test]
+fn test_ignore_punctuation_initials() {
+    fn check<T: flashtext2::Tokenizer>(tokenizer: T) {
+        let mut kp = KeywordProcessor::builder()
+            .tokenizer(flashtext2::IgnorePunctuation(tokenizer))
+            .build();
+        kp.add_keywords_from_iter(["U.S. Department of Justice", "e.g.", "can't"]);
+
+        let text = "The US Department of Justice";
+        assert_eq!(
+            kp.extract_keywords_with_span(text).collect::<Vec<_>>(),
+            [("U.S. Department of Justice", 4, 28)]
+        );
+        let text = "The U.S. Department of Justice, eg";
+        assert_eq!(
+            kp.extract_keywords_with_span(text).collect::<Vec<_>>(),
+            [("U.S. Department of Justice", 4, 30), ("e.g.", 32, 34)]
+        );
+        // only single letters are joined
+        assert_eq!(kp.extract_keywords("U.Sc Department of Justice, cant").count(), 0);
+    }
+    check(flashtext2::WordBoundTokenizer);
+    check(flashtext2::SimpleTokenizer);
+    check(flashtext2::AsciiTokenizer);
+}
+
+
+#[test]
+fn test_extract_keywords_from_docs() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_with_clean_word_from_iter([("py", "Python"), ("rs", "Rust")]);
+
+    let docs = vec![String::from("py and rs"), String::new(), String::from("rs")];
+    // the documents are dropped after they are processed, but not the matches
+    let results: Vec<_> = kp
+        .extract_keywords_from_docs(docs)
+        .map(|(idx, keywords)| (idx, keywords.into_iter().map(<(_, _, _)>::from).collect()))
+        .collect::<Vec<(_, Vec<_>)>>();
+    assert_eq!(
+        results,
+        [
+            (0, vec![("Python", 0, 2), ("Rust", 7, 9)]),
+            (1, vec![]),
+            (2, vec![("Rust", 0, 2)]),
+        ]
+    );
+    let (idx, keywords) = kp.extract_keywords_from_docs(["rs rs"]).next().unwrap();
+    assert_eq!(idx, 0);
+    assert_eq!(keywords, [("Rust", 0, 2), ("Rust", 3, 5)]);
+    assert_eq!(kp.extract_keywords_from_docs(Vec::<String>::new()).count(), 0);
+}
+
+
+#[test]
+fn test_highlight_keywords() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keyword_with_clean_word("py", "Python");
+    kp.add_keyword("New York");
+
+    assert_eq!(
+        kp.highlight_keywords("I use PY in new york.", "<mark>", "</mark>"),
+        "I use <mark>PY</mark> in <mark>new york</mark>."
+    );
+    assert_eq!(kp.highlight_keywords("py", "**", "**"), "**py**");
+    assert_eq!(kp.highlight_keywords("nothing", "**", "**"), "nothing");
+}
+
+
+#[test]
+fn test_has_any_keyword() {
+    let mut kp = KeywordProcessor::new();
+    assert!(!kp.has_any_keyword("anything"));
+
+    kp.add_keywords_from_iter(["spam", "eggs and ham"]);
+    assert!(kp.has_any_keyword("spam"));
+    assert!(kp.has_any_keyword("green eggs and ham"));
+    assert!(!kp.has_any_keyword("green eggs and"));
+    assert!(!kp.has_any_keyword("Spam"));
+    assert!(!kp.has_any_keyword(""));
+
+    let kp = KeywordProcessor::<u32>::default();
+    assert!(!kp.has_any_keyword("spam"));
+}
+
+
+#[test]
+fn test_count_keywords() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter(["big apple", "apple pie", "pie", "a b c", "b"]);
+
+    let text = "the big apple pie, apple pie and pie. ".repeat(10);
+    for text in [text.as_str(), "", "no keywords", "a b", "a b d", "pie"] {
+        assert_eq!(kp.count_keywords(text), kp.extract_keywords(text).count(), "{text}");
+    }
+    assert_eq!(kp.count_keywords(&text), 40);
+
+    kp.add_keyword_with_priority("apple pie", "apple pie".to_string(), 1);
+    assert_eq!(kp.count_keywords("the big apple pie"), 1);
+}
+
+
+#[test]
+fn test_for_each_keyword() {
+    use std::borrow::Cow;
+
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter(["spam", "eggs", "ham"]);
+    let text = String::from("spam, eggs, spam and ham");
+
+    let mut keywords = Vec::new();
+    kp.for_each_keyword(&text, |m| {
+        keywords.push(m);
+        ControlFlow::Continue(())
+    });
+    assert_eq!(keywords, [("spam", 0, 4), ("eggs", 6, 10), ("spam", 12, 16), ("ham", 21, 24)]);
+    // the matched text is borrowed, not copied
+    assert!(keywords.iter().all(|m| matches!(m.keyword, Cow::Borrowed(_))));
+    assert!(std::ptr::eq(keywords[1].keyword.as_ptr(), text[6..].as_ptr()));
+
+    let mut count = 0;
+    kp.for_each_keyword("spam spam eggs spam", |m| {
+        count += 1;
+        if m.clean_word == "eggs" {
+            ControlFlow::Break(())
+        } else {
+            ControlFlow::Continue(())
+        }
+    });
+    assert_eq!(count, 3);
+}
+
+
+#[test]
+fn test_replace_keywords_with_template() {
+    use flashtext2::{Template, TemplateError};
+
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keyword_with_clean_word("py", "Python");
+    kp.add_keyword_with_clean_word("rs", "Rust");
+
+    let template = Template::new("<abbr title=\"{clean}\">{keyword}</abbr>").unwrap();
+    assert_eq!(
+        kp.replace_keywords_with_template("PY and rs", &template),
+        "<abbr title=\"Python\">PY</abbr> and <abbr title=\"Rust\">rs</abbr>"
+    );
+    let template = Template::new("{{{clean}}}").unwrap();
+    assert_eq!(kp.replace_keywords_with_template("py", &template), "{Python}");
+    assert_eq!(template.render("ü", ""), "{ü}");
+    let template = Template::new("").unwrap();
+    assert_eq!(kp.replace_keywords_with_template("py!", &template), "!");
+
+    assert_eq!(
+        Template::new("{name}"),
+        Err(TemplateError::UnknownPlaceholder("name".to_string()))
+    );
+    assert_eq!(Template::new("a {clean"), Err(TemplateError::Unclosed(2)));
+    assert_eq!(Template::new("a }"), Err(TemplateError::Unopened(2)));
+    assert_eq!(TemplateError::Unclosed(2).to_string(), "unclosed `{` at 2");
+}
+
+
+#[test]
+fn test_replace_keywords_to() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keyword_with_clean_word("py", "Python");
+    kp.add_keyword_with_clean_word("rs", "Rust");
+
+    for text in ["py and rs!", "", "nothing", "py"] {
+        let mut bytes = Vec::new();
+        kp.replace_keywords_to(text, &mut bytes).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), kp.replace_keywords(text));
+
+        let mut string = String::from(">");
+        kp.replace_keywords_to_fmt(text, &mut string).unwrap();
+        assert_eq!(string, format!(">{}", kp.replace_keywords(text)));
+    }
+
+    let mut full = [0; 8];
+    let err = kp.replace_keywords_to("py and rs", full.as_mut_slice()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+}
+
+
+#[test]
+fn test_replace_keywords_in_place() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_with_clean_word_from_iter([
+        ("python", "py"),
+        ("rust", "rs"),
+        ("js", "JavaScript"),
+        ("c", "C"),
+        ("ü", "u"),
+    ]);
+
+    for text in [
+        "python and rust, or c", // shorter
+        "js and c",              // longer
+        "js then python",        // longer, then shorter
+        "python then js",        // shorter, then longer
+        "python then js then js",
+        "ü and über",
+        "",
+        "nothing",
+    ] {
+        let mut string = text.to_string();
+        kp.replace_keywords_in_place(&mut string);
+        assert_eq!(string, kp.replace_keywords(text), "{text}");
+    }
+}
+
+
+#[test]
+fn test_replace_keywords_cow() {
+    use std::borrow::Cow;
+
+    let mut kp = KeywordProcessor::new();
+    kp.add_keyword_with_clean_word("py", "Python");
+
+    assert!(matches!(kp.replace_keywords_cow("nothing here"), Cow::Borrowed("nothing here")));
+    assert!(matches!(kp.replace_keywords_cow(""), Cow::Borrowed("")));
+    let replaced = kp.replace_keywords_cow("I love py");
+    assert!(matches!(replaced, Cow::Owned(_)));
+    assert_eq!(replaced, "I love Python");
+}
+
+
+#[test]
+fn test_add_keyword_with_replacement() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keyword_with_replacement("rust", "Rust", "<a href=\"/rust\">Rust</a>");
+    kp.add_keyword_with_clean_word("py", "Python");
+
+    let text = "RUST or py";
+    let expected = "<a href=\"/rust\">Rust</a> or Python";
+    assert_eq!(kp.extract_keywords(text).collect::<Vec<_>>(), ["Rust", "Python"]);
+    assert_eq!(kp.replace_keywords(text), expected);
+    assert_eq!(kp.replace_keywords_cow(text), expected);
+    let mut string = String::new();
+    kp.replace_keywords_to_fmt(text, &mut string).unwrap();
+    assert_eq!(string, expected);
+    let mut string = text.to_string();
+    kp.replace_keywords_in_place(&mut string);
+    assert_eq!(string, expected);
+
+    // the clean word can be overwritten, but the replacement is kept
+    kp.add_keyword_with_clean_word("Rust", "Rust lang");
+    assert_eq!(kp.replace_keywords(text), expected);
+
+    assert!(kp.remove_keyword("rust"));
+    kp.add_keyword("rust");
+    assert_eq!(kp.replace_keywords(text), "rust or Python");
+}
+
+
+#[test]
+fn test_replace_keywords_matching_case() {
+    let mut kp = case_insensitive::KeywordProcessor::new();
+    kp.add_keyword_with_clean_word("apple", "Fruit");
+    kp.add_keyword_with_clean_word("new york", "big apple");
+    kp.add_keyword_with_clean_word("c", "C lang");
+
+    assert_eq!(
+        kp.replace_keywords_matching_case("apple, Apple, APPLE, aPPle"),
+        "fruit, Fruit, FRUIT, Fruit"
+    );
+    assert_eq!(
+        kp.replace_keywords_matching_case("New york and NEW YORK"),
+        "Big apple and BIG APPLE"
+    );
+    assert_eq!(kp.replace_keywords_matching_case("C and c"), "C lang and c lang");
+}
+
+
+#[test]
+fn test_replace_keywords_limited() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keyword_with_clean_word("rust", "[Rust](/rust)");
+
+    let text = "rust, rust and rust";
+    assert_eq!(kp.replace_keywords_limited(text, 0), text);
+    assert_eq!(kp.replace_keywords_limited(text, 1), "[Rust](/rust), rust and rust");
+    assert_eq!(kp.replace_keywords_limited(text, 2), "[Rust](/rust), [Rust](/rust) and rust");
+    assert_eq!(kp.replace_keywords_limited(text, 5), kp.replace_keywords(text));
+}
+
+
+#[test]
+fn test_redact_keywords() {
+    use flashtext2::Mask;
+
+    let mut kp = KeywordProcessor::builder().case_mode(CaseMode::Insensitive).build::<u32>();
+    kp.add_keyword_with_value("John Smith", 1);
+    kp.add_keyword_with_value("Zoë", 2);
+
+    let text = "john smith met Zoë.";
+    assert_eq!(kp.redact_keywords(text, "[REDACTED]"), "[REDACTED] met [REDACTED].");
+    assert_eq!(kp.redact_keywords(text, Mask::Repeat('*')), "********** met ***.");
+    assert_eq!(kp.redact_keywords(text, '█'), "██████████ met ███.");
+    assert_eq!(kp.redact_keywords("nobody", '*'), "nobody");
+}
+
+
+#[test]
+fn test_replace_keywords_with_edits() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keyword_with_clean_word("New York", "NYC");
+    kp.add_keyword_with_clean_word("LA", "Los Angeles");
+
+    let text = "From New York to LA.";
+    let (replaced, edits) = kp.replace_keywords_with_edits(text);
+    assert_eq!(replaced, kp.replace_keywords(text));
+    assert_eq!(replaced, "From NYC to Los Angeles.");
+    assert_eq!(edits.len(), 2);
+    for edit in &edits {
+        assert_eq!(&replaced[edit.replaced.clone()], edit.clean_word);
+    }
+    assert_eq!((edits[0].original.clone(), edits[0].replaced.clone()), (5..13, 5..8));
+    assert_eq!((edits[1].original.clone(), edits[1].replaced.clone()), (17..19, 12..23));
+
+    let (replaced, edits) = kp.replace_keywords_with_edits("nothing");
+    assert_eq!((replaced.as_str(), edits.len()), ("nothing", 0));
+}
+
+
+#[test]
+fn test_to_double_array() {
+    let mut kp = KeywordProcessor::builder().case_mode(CaseMode::Insensitive).build();
+    kp.add_keywords_with_clean_word_from_iter([
+        ("New York", "NY"),
+        ("New York City", "NYC"),
+        ("york", "York"),
+        ("city", "City"),
+        ("Maße", "Maße"),
+        ("a b c d", "abcd"),
+        ("b", "b"),
+    ]);
+    let da = kp.to_double_array();
+    assert_eq!(da.len(), kp.len());
+    assert_eq!(da.case_mode(), CaseMode::Insensitive);
+
+    let texts = [
+        "new york city, NEW YORK and the city of York",
+        "MASSE and maße",
+        "a b c a b c d b",
+        "New",
+        "",
+    ];
+    for text in texts {
+        assert_eq!(
+            da.extract_keywords_with_span(text).collect::<Vec<_>>(),
+            kp.extract_keywords_with_span(text).collect::<Vec<_>>()
+        );
+    }
+    assert_eq!(da.get_clean_word("NEW york"), Some("NY"));
+    assert!(da.contains("A b C d"));
+    assert!(!da.contains("a b c"));
+    assert!(!da.contains("unknown"));
+
+    // many keywords that share their first tokens
+    let mut kp = KeywordProcessor::<usize>::default();
+    for i in 0..1_000 {
+        kp.add_keyword_with_value(&format!("{} {} {}", i % 7, i % 13, i), i);
+    }
+    let da = kp.to_double_array();
+    assert!(da.memory_usage_with(|_| 0) < kp.memory_usage_with(|_| 0));
+    let text = "3 4 17 and 0 0 0 and 6 12 90 but 6 12 91";
+    assert_eq!(
+        da.extract_keywords_with_value(text).map(|(_, &i, _, _)| i).collect::<Vec<_>>(),
+        [17, 0, 90]
+    );
+}
+
+
+#[test]
+fn test_to_radix_tree() {
+    let keywords = [
+        ("New York", "NY"),
+        ("New York City", "NYC"),
+        ("york", "York"),
+        ("city", "City"),
+        ("Maße", "Maße"),
+        ("\u{212A}elvin", "Kelvin"),
+        ("a b c d", "abcd"),
+        ("b", "b"),
+    ];
+    let texts = [
+        "new york city, NEW YORK and the city of York",
+        "MASSE and maße and MAẞE",
+        "kelvin KELVIN \u{212A}elvin",
+        "a b c a b c d b",
+        "New",
+        "",
+    ];
+    for case_mode in [CaseMode::Sensitive, CaseMode::AsciiInsensitive, CaseMode::Insensitive] {
+        let mut kp = KeywordProcessor::builder().case_mode(case_mode).build();
+        kp.add_keywords_with_clean_word_from_iter(keywords);
+        let radix = kp.to_radix_tree();
+        assert_eq!(radix.len(), kp.len());
+        assert_eq!(radix.case_mode(), case_mode);
+        for text in texts {
+            assert_eq!(
+                radix.extract_keywords_with_span(text).collect::<Vec<_>>(),
+                kp.extract_keywords_with_span(text).collect::<Vec<_>>()
+            );
+        }
+        for word in ["NEW york", "New York", "kelvin", "MASSE", "A b C d", "a b c", "unknown"] {
+            assert_eq!(radix.get_clean_word(word), kp.get_clean_word(word));
+        }
+    }
+
+    // many keywords that share their first tokens
+    let mut kp = KeywordProcessor::<usize>::default();
+    for i in 0..1_000 {
+        kp.add_keyword_with_value(&format!("{} {} {}", i % 7, i % 13, i), i);
+    }
+    let radix = kp.to_radix_tree();
+    assert!(radix.node_count() < 2 * kp.len());
+    let text = "3 4 17 and 0 0 0 and 6 12 90 but 6 12 91";
+    assert_eq!(
+        radix.extract_keywords_with_value(text).map(|(_, &i, _, _)| i).collect::<Vec<_>>(),
+        [17, 0, 90]
+    );
+}
+
+
+#[test]
+fn test_mapped_keyword_processor() {
+    use flashtext2::{MappedError, MappedKeywordProcessor, SimpleTokenizer};
+
+    let mut kp = KeywordProcessor::builder().case_mode(CaseMode::Insensitive).build();
+    kp.add_keywords_with_clean_word_from_iter([
+        ("New York", "NY"),
+        ("New York City", "NYC"),
+        ("york", "York"),
+        ("Maße", "Maße"),
+        ("café", "Café"),
+    ]);
+    // enough first tokens for the root to get a table of children
+    kp.add_keywords_from_iter((b'!'..=b'~').map(|b| format!("{}9", b as char)));
+    let bytes = kp.to_radix_tree().to_bytes();
+    let mapped = MappedKeywordProcessor::new(&bytes).unwrap();
+    assert_eq!(mapped.len(), kp.len());
+    assert_eq!(mapped.case_mode(), CaseMode::Insensitive);
+
+    let text = "new york city, NEW YORK and the CAFÉ of York, MASSE 29 and ~9";
+    assert_eq!(
+        mapped.extract_keywords_with_span(text).collect::<Vec<_>>(),
+        kp.extract_keywords_with_span(text).collect::<Vec<_>>()
+    );
+    assert_eq!(mapped.get_clean_word("NEW york"), Some("NY"));
+    assert!(mapped.contains("X9"));
+    assert!(!mapped.contains("New"));
+
+    // a copy at another (unaligned) address reads the same
+    let mut shifted = vec![0];
+    shifted.extend(&bytes);
+    let mapped = MappedKeywordProcessor::new(&shifted[1..]).unwrap();
+    assert_eq!(mapped.extract_keywords(text).count(), kp.extract_keywords(text).count());
+
+    let mut kp = KeywordProcessor::with_tokenizer(SimpleTokenizer);
+    kp.add_keyword("foo bar");
+    let bytes = kp.to_radix_tree().to_bytes();
+    let mapped = MappedKeywordProcessor::with_tokenizer(&bytes, SimpleTokenizer).unwrap();
+    assert_eq!(mapped.extract_keywords("a foo bar").collect::<Vec<_>>(), ["foo bar"]);
+
+    assert_eq!(MappedKeywordProcessor::new(&bytes[..10]).unwrap_err(), MappedError::Truncated);
+    assert_eq!(
+        MappedKeywordProcessor::new(&bytes[..bytes.len() - 1]).unwrap_err(),
+        MappedError::Truncated
+    );
+    let mut longer = bytes.clone();
+    longer.push(0);
+    assert_eq!(MappedKeywordProcessor::new(&longer).unwrap_err(), MappedError::TrailingBytes);
+    let mut invalid = bytes.clone();
+    invalid[0] = b'X';
+    assert_eq!(MappedKeywordProcessor::new(&invalid).unwrap_err(), MappedError::InvalidMagic);
+}
+
+#[test]
+fn test_into_frozen() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New York", "York", "Paris", "Rome", "New Delhi"]);
+    kp.add_keyword_with_priority("York City", "York City".to_string(), 1);
+    assert!(kp.remove_keyword("Paris"));
+    kp.reserve_prefix("Paris", 10);
+    let memory_usage = kp.memory_usage();
+    let text = "New York City and Rome, new Delhi and New Delhi";
+    let expected: Vec<_> = kp.extract_keywords_with_span(text).collect();
+
+    let frozen = kp.clone().into_frozen();
+    assert!(frozen.memory_usage() < memory_usage);
+    assert_eq!(frozen.len(), 5);
+    // the nodes that don't lead to a keyword are removed
+    assert_eq!(frozen.node_count(), 8);
+    // the priorities are kept
+    assert_eq!(frozen.extract_keywords_with_span(text).collect::<Vec<_>>(), expected);
+    assert_eq!(
+        frozen.extract_keywords(text).collect::<Vec<_>>(),
+        ["York City", "Rome", "New Delhi"]
+    );
+    assert!(frozen.contains("New York"));
+    assert!(!frozen.contains("New"));
+    assert!(!frozen.contains("Paris"));
+    assert_eq!(frozen.get_clean_word("York City"), Some("York City"));
+
+    let mut kp = KeywordProcessor::builder().case_mode(CaseMode::Insensitive).build();
+    kp.add_keywords_from_iter(["a", "a b", "b c d", "c", "a b c d e", "d e", "b a b"]);
+    let text = "A b c d e b a b c a b C d x";
+    let expected: Vec<_> = kp.extract_keywords_with_span(text).collect();
+    let frozen = kp.clone().into_frozen();
+    assert_eq!(frozen.extract_keywords_with_span(text).collect::<Vec<_>>(), expected);
+}
+
+
+#[test]
+fn test_frozen_is_send_sync() {
+    use flashtext2::{FrozenKeywordProcessor, SimpleTokenizer};
+
+    const fn assert_send_sync<T: Send + Sync>() {}
+    const _: () = assert_send_sync::<FrozenKeywordProcessor>();
+    const _: () = assert_send_sync::<FrozenKeywordProcessor<usize, SimpleTokenizer>>();
+
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter(["New York", "Rome"]);
+    let frozen = std::sync::Arc::new(kp.into_frozen());
+    let threads: Vec<_> = (0..2)
+        .map(|_| {
+            let frozen = frozen.clone();
+            std::thread::spawn(move || frozen.extract_keywords("New York and Rome").count())
+        })
+        .collect();
+    for thread in threads {
+        assert_eq!(thread.join().unwrap(), 2);
+    }
+}
+
+
+#[test]
+fn test_freeze() {
+    let mut kp = KeywordProcessor::builder().case_mode(CaseMode::Insensitive).build();
+    kp.add_keywords_from_iter(["a", "a b", "b c d", "c", "a b c d e", "d e", "b a b"]);
+    let compiled = kp.freeze();
+    assert_eq!(compiled.len(), kp.len());
+    assert!(compiled.contains("A B"));
+    assert_eq!(compiled.get_clean_word("b C d"), Some("b c d"));
+    assert!(!compiled.contains("b c"));
+    assert!(!compiled.contains(""));
+
+    // every text of up to 7 tokens among "a".."e" (and one that isn't in any keyword)
+    let letters = ["a", "B", "c", "d", "e", "x"];
+    for n in 0..6_usize.pow(7) {
+        let mut n = n;
+        let mut text = String::new();
+        while n > 0 {
+            text += letters[n % 6];
+            text += " ";
+            n /= 6;
+        }
+        assert_eq!(
+            compiled.extract_keywords_with_span(&text).collect::<Vec<_>>(),
+            kp.extract_keywords_with_span(&text).collect::<Vec<_>>(),
+            "{text:?}"
+        );
+    }
+
+    // a text where the trie is traversed up to the last token from each token
+    let mut kp = KeywordProcessor::new();
+    kp.add_keyword("a ".repeat(500) + "b");
+    let compiled = kp.freeze();
+    let text = "a ".repeat(1_000) + "b";
+    let keyword = kp.keywords().next().unwrap();
+    let matches: Vec<_> = compiled.extract_keywords_with_span(&text).collect();
+    assert_eq!(matches, [(keyword.as_str(), 1000, 2001)]);
+}
+
+
+#[test]
+fn test_extract_keywords_after_partial_match() {
+    let mut kp = KeywordProcessor::new();
+    kp.add_keywords_from_iter(["a b", "a b c d e", "c", "b c d"]);
+    // the traversal of "a b c d e" fails at "x", and the one of "b c d" reaches the end
+    assert_eq!(kp.extract_keywords("a b c d x c").collect::<Vec<_>>(), ["a b", "c", "c"]);
+    assert_eq!(kp.extract_keywords("b c c").collect::<Vec<_>>(), ["c", "c"]);
+    assert_eq!(kp.count_keywords("b c c"), 2);
+}
+
+
+#[test]
+fn test_minimize() {
+    let mut kp = KeywordProcessor::builder().case_mode(CaseMode::Insensitive).build();
+    for brand in ["Acme", "Globex"] {
+        for model in 1..=20 {
+            for color in ["red", "blue", "black"] {
+                kp.add_keyword_with_clean_word(&format!("{brand} {model} {color}"), brand);
+            }
+            kp.add_keyword_with_clean_word(&format!("{brand} {model}"), brand);
+        }
+    }
+    kp.add_keywords_from_iter(["red", "blue", "2 red"]);
+    let compiled = kp.freeze();
+    let mut minimized = compiled.clone();
+    minimized.minimize();
+    assert!(minimized.node_count() < compiled.node_count() / 4);
+    assert_eq!(minimized.len(), compiled.len());
+    assert!(minimized.memory_usage() < compiled.memory_usage());
+    assert_eq!(minimized.get_clean_word("ACME 7 blue"), Some("Acme"));
+    assert_eq!(minimized.get_clean_word("2 red"), Some("2 red"));
+    assert!(!minimized.contains("Acme 7 green"));
+
+    let text = "acme 2 red, Globex 12 blue, 2 red, acme 7 green, red and globex 3";
+    let matches: Vec<_> = minimized.extract_keywords_with_span(text).collect();
+    assert_eq!(matches, compiled.extract_keywords_with_span(text).collect::<Vec<_>>());
+    assert_eq!(matches, kp.extract_keywords_with_span(text).collect::<Vec<_>>());
+    assert_eq!(matches.len(), 6);
 
-    let mut kp = KeywordProcessor::new(true);
-    kp.add_keyword("Hello", "Hello");
-    assert_eq!(kp.extract_keywords("Hello")[0], "Hello");
+    // minimizing again doesn't change anything
+    let node_count = minimized.node_count();
+    minimized.minimize();
+    assert_eq!(minimized.node_count(), node_count);
 }
@@ -0,0 +1,27 @@
+#![cfg(feature = "memory-stats")]
+
+use flashtext2::{KeywordProcessor, TrackingAllocator};
+
+// in its own test binary, since it replaces the global allocator
+#[global_allocator]
+static ALLOCATOR: TrackingAllocator = TrackingAllocator::new(std::alloc::System);
+
+
+#[test]
+fn test_memory_stats() {
+    let mut kp = KeywordProcessor::new();
+    assert_eq!(kp.memory_stats(), None);
+    kp.add_keyword("not measured");
+    assert_eq!(kp.memory_stats(), None);
+
+    kp.add_keywords_from_iter((0..1_000).map(|i| format!("keyword {i}")));
+    let stats = kp.memory_stats().unwrap();
+    assert!(stats.peak > stats.start);
+    assert!(stats.peak_increase() >= kp.memory_usage());
+
+    // the start is kept from the first call, and the peak only grows
+    kp.add_keywords_with_clean_word_from_iter([("a", "b")]);
+    let new_stats = kp.memory_stats().unwrap();
+    assert_eq!(new_stats.start, stats.start);
+    assert!(new_stats.peak >= stats.peak);
+}